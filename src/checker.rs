@@ -0,0 +1,390 @@
+use std::{cell::RefCell, convert::Infallible, fmt};
+
+use crate::{
+    errors::Errors,
+    expr::{Expr, MatchArm, Visitor},
+    token::{Object, Token, TokenType},
+};
+
+/// What the checker knows about the value of an expression. `Dynamic`
+/// means it can't tell, and is compatible with everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    Int,
+    Float,
+    Decimal,
+    String,
+    Bool,
+    Nil,
+    List,
+    Map,
+    Dynamic,
+}
+
+impl Type {
+    fn of(value: &Object) -> Self {
+        match value {
+            Object::Int(_) | Object::BigInt(_) => Type::Int,
+            Object::Number(_) => Type::Float,
+            Object::Decimal(_) => Type::Decimal,
+            Object::String(_) | Object::Identifier(_) => Type::String,
+            Object::Bool(_) => Type::Bool,
+            Object::List(_) => Type::List,
+            Object::Map(_) => Type::Map,
+            Object::Native(_) => Type::Dynamic,
+            Object::Nil => Type::Nil,
+        }
+    }
+
+    fn is_number(self) -> bool {
+        matches!(
+            self,
+            Type::Int | Type::Float | Type::Decimal | Type::Dynamic
+        )
+    }
+
+    fn is_int(self) -> bool {
+        matches!(self, Type::Int | Type::Dynamic)
+    }
+
+    /// The type of a value that is either `self` or `other`.
+    fn join(self, other: Type) -> Type {
+        if self == other {
+            self
+        } else {
+            Type::Dynamic
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Type::Int => "int",
+            Type::Float => "float",
+            Type::Decimal => "decimal",
+            Type::String => "string",
+            Type::Bool => "bool",
+            Type::Nil => "nil",
+            Type::List => "list",
+            Type::Map => "map",
+            Type::Dynamic => "dynamic",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Infers the types of an expression tree before it runs and reports the
+/// operations that are sure to fail, such as `"a" - 1`. Anything it can't
+/// infer, like a variable, is `Dynamic` and never reported.
+#[derive(Default)]
+pub struct TypeChecker {
+    errors: RefCell<Errors>,
+}
+
+impl TypeChecker {
+    /// Checks `expr`, returning whether it is free of type errors.
+    pub fn check(&self, expr: &Expr) -> bool {
+        let Ok(_) = self.infer(expr);
+        !self.errors.borrow().had_error
+    }
+
+    fn infer(&self, expr: &Expr) -> Result<Type, Infallible> {
+        expr.accept(self)
+    }
+
+    fn error(&self, token: &Token, message: &str) -> Type {
+        self.errors.borrow_mut().error_token(token, message);
+        Type::Dynamic
+    }
+
+    fn binary(&self, operator: &Token, left: Type, right: Type) -> Type {
+        match operator.r#type {
+            TokenType::Plus => match (left, right) {
+                (Type::String, Type::String | Type::Dynamic) | (Type::Dynamic, Type::String) => {
+                    Type::String
+                }
+                _ if left.is_number() && right.is_number() => {
+                    self.arithmetic(operator, left, right)
+                }
+                _ => self.error(
+                    operator,
+                    &format!(
+                        "Operands must be two numbers or two strings, not {left} and {right}."
+                    ),
+                ),
+            },
+            TokenType::Minus | TokenType::Star | TokenType::Slash | TokenType::Percent => {
+                if left.is_number() && right.is_number() {
+                    self.arithmetic(operator, left, right)
+                } else {
+                    self.error(
+                        operator,
+                        &format!("Operands must be numbers, not {left} and {right}."),
+                    )
+                }
+            }
+            TokenType::StarStar => match (left, right) {
+                _ if !left.is_number() || !right.is_number() => self.error(
+                    operator,
+                    &format!("Operands must be numbers, not {left} and {right}."),
+                ),
+                // A negative exponent gives a float.
+                (Type::Int, Type::Int) => Type::Dynamic,
+                _ => self.arithmetic(operator, left, right),
+            },
+            TokenType::Ampersand
+            | TokenType::Pipe
+            | TokenType::Caret
+            | TokenType::LessLess
+            | TokenType::GreaterGreater => {
+                if left.is_int() && right.is_int() {
+                    Type::Int
+                } else {
+                    self.error(
+                        operator,
+                        &format!("Operands must be ints, not {left} and {right}."),
+                    )
+                }
+            }
+            TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual => match (left, right) {
+                (Type::Decimal, Type::Float) | (Type::Float, Type::Decimal) => {
+                    self.error(operator, "Cannot mix decimal and float operands.")
+                }
+                _ if left.is_number() && right.is_number() => Type::Bool,
+                _ => self.error(
+                    operator,
+                    &format!("Operands must be numbers, not {left} and {right}."),
+                ),
+            },
+            TokenType::BangEqual | TokenType::EqualEqual => Type::Bool,
+            TokenType::Comma => right,
+            _ => Type::Dynamic,
+        }
+    }
+
+    /// The result of arithmetic on two numeric types.
+    fn arithmetic(&self, operator: &Token, left: Type, right: Type) -> Type {
+        match (left, right) {
+            (Type::Decimal, Type::Float) | (Type::Float, Type::Decimal) => {
+                self.error(operator, "Cannot mix decimal and float operands.")
+            }
+            (Type::Dynamic, _) | (_, Type::Dynamic) => Type::Dynamic,
+            (Type::Decimal, _) | (_, Type::Decimal) => Type::Decimal,
+            (Type::Float, _) | (_, Type::Float) => Type::Float,
+            _ => Type::Int,
+        }
+    }
+
+    fn index(&self, object: Type, bracket: &Token, index: Type) {
+        match object {
+            Type::List if !index.is_int() => {
+                self.error(bracket, &format!("List index must be an int, not {index}."));
+            }
+            Type::Map => self.map_key(bracket, index),
+            Type::List | Type::Dynamic => {}
+            _ => {
+                self.error(
+                    bracket,
+                    &format!("Only lists and maps can be indexed, not {object}."),
+                );
+            }
+        }
+    }
+
+    fn map_key(&self, token: &Token, key: Type) {
+        if matches!(key, Type::List | Type::Map) {
+            self.error(token, &format!("Map key of type {key} is not hashable."));
+        }
+    }
+}
+
+impl Visitor<Type, Infallible> for TypeChecker {
+    fn visit_binary_expr(
+        &self,
+        left: &Expr,
+        operator: &Token,
+        right: &Expr,
+    ) -> Result<Type, Infallible> {
+        let left = self.infer(left)?;
+        let right = self.infer(right)?;
+        Ok(self.binary(operator, left, right))
+    }
+
+    fn visit_call_expr(
+        &self,
+        callee: &Expr,
+        paren: &Token,
+        arguments: &[Expr],
+    ) -> Result<Type, Infallible> {
+        let callee = self.infer(callee)?;
+        for argument in arguments {
+            self.infer(argument)?;
+        }
+        // Only natives can be called, and they are only reached through
+        // variables, so any type the checker knows isn't callable.
+        if callee != Type::Dynamic {
+            self.error(paren, &format!("Can only call functions, not {callee}."));
+        }
+        Ok(Type::Dynamic)
+    }
+
+    fn visit_compound_assign_expr(
+        &self,
+        target: &Expr,
+        operator: &Token,
+        value: &Expr,
+    ) -> Result<Type, Infallible> {
+        let target = self.infer(target)?;
+        let value = self.infer(value)?;
+        Ok(self.binary(&operator.compound_operator(), target, value))
+    }
+
+    fn visit_for_expr(
+        &self,
+        keyword: &Token,
+        _name: &Token,
+        iterable: &Expr,
+        body: &Expr,
+    ) -> Result<Type, Infallible> {
+        let iterable = self.infer(iterable)?;
+        if !matches!(
+            iterable,
+            Type::List | Type::Map | Type::String | Type::Dynamic
+        ) {
+            self.error(
+                keyword,
+                &format!("Only lists, maps and strings can be iterated, not {iterable}."),
+            );
+        }
+        self.infer(body)?;
+        Ok(Type::Nil)
+    }
+
+    fn visit_grouping_expr(&self, expr: &Expr) -> Result<Type, Infallible> {
+        self.infer(expr)
+    }
+
+    fn visit_index_expr(
+        &self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+    ) -> Result<Type, Infallible> {
+        let object = self.infer(object)?;
+        let index = self.infer(index)?;
+        self.index(object, bracket, index);
+        Ok(Type::Dynamic)
+    }
+
+    fn visit_interpolation_expr(&self, parts: &[Expr]) -> Result<Type, Infallible> {
+        for part in parts {
+            self.infer(part)?;
+        }
+        Ok(Type::String)
+    }
+
+    fn visit_list_expr(&self, elements: &[Expr]) -> Result<Type, Infallible> {
+        for element in elements {
+            self.infer(element)?;
+        }
+        Ok(Type::List)
+    }
+
+    fn visit_literal_expr(&self, value: &Object) -> Result<Type, Infallible> {
+        Ok(Type::of(value))
+    }
+
+    fn visit_map_expr(&self, brace: &Token, entries: &[(Expr, Expr)]) -> Result<Type, Infallible> {
+        for (key, value) in entries {
+            let key = self.infer(key)?;
+            self.map_key(brace, key);
+            self.infer(value)?;
+        }
+        Ok(Type::Map)
+    }
+
+    fn visit_match_expr(
+        &self,
+        _keyword: &Token,
+        subject: &Expr,
+        arms: &[MatchArm],
+    ) -> Result<Type, Infallible> {
+        self.infer(subject)?;
+        let mut result = None;
+        for arm in arms {
+            if let Some(guard) = &arm.guard {
+                self.infer(guard)?;
+            }
+            let body = self.infer(&arm.body)?;
+            result = Some(result.map_or(body, |result: Type| result.join(body)));
+        }
+        Ok(result.unwrap_or(Type::Dynamic))
+    }
+
+    fn visit_set_index_expr(
+        &self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<Type, Infallible> {
+        let object = self.infer(object)?;
+        let index = self.infer(index)?;
+        self.index(object, bracket, index);
+        self.infer(value)
+    }
+
+    fn visit_throw_expr(&self, _keyword: &Token, value: &Expr) -> Result<Type, Infallible> {
+        self.infer(value)?;
+        Ok(Type::Dynamic)
+    }
+
+    fn visit_try_expr(
+        &self,
+        body: &Expr,
+        catch: Option<&(Token, Box<Expr>)>,
+        finally: Option<&Expr>,
+    ) -> Result<Type, Infallible> {
+        let mut result = self.infer(body)?;
+        if let Some((_, handler)) = catch {
+            result = result.join(self.infer(handler)?);
+        }
+        if let Some(finally) = finally {
+            self.infer(finally)?;
+        }
+        Ok(result)
+    }
+
+    fn visit_unary_expr(&self, operator: &Token, right: &Expr) -> Result<Type, Infallible> {
+        let right = self.infer(right)?;
+        Ok(match operator.r#type {
+            TokenType::Minus if right.is_number() => right,
+            TokenType::Minus => {
+                self.error(operator, &format!("Operand must be a number, not {right}."))
+            }
+            TokenType::Tilde if right.is_int() => Type::Int,
+            TokenType::Tilde => {
+                self.error(operator, &format!("Operand must be an int, not {right}."))
+            }
+            _ => Type::Bool,
+        })
+    }
+
+    fn visit_update_expr(
+        &self,
+        target: &Expr,
+        operator: &Token,
+        _prefix: bool,
+    ) -> Result<Type, Infallible> {
+        let target = self.infer(target)?;
+        Ok(self.binary(&operator.compound_operator(), target, Type::Int))
+    }
+
+    fn visit_variable_expr(&self, _name: &Token) -> Result<Type, Infallible> {
+        Ok(Type::Dynamic)
+    }
+}
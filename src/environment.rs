@@ -0,0 +1,34 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{
+    token::{Object, Token},
+    InterpreterError,
+};
+
+/// The variables of one scope, looked up through its enclosing scopes.
+#[derive(Default, Debug)]
+pub struct Environment {
+    values: HashMap<String, Object>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+
+    pub fn define(&mut self, name: &str, value: Object) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    pub fn get(&self, name: &Token) -> Result<Object, InterpreterError> {
+        match (self.values.get(&name.loc.lexeme), &self.enclosing) {
+            (Some(value), _) => Ok(value.clone()),
+            (None, Some(enclosing)) => enclosing.borrow().get(name),
+            (None, None) => Err(InterpreterError::UndefinedVariable(name.loc.lexeme.clone())),
+        }
+    }
+}
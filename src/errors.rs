@@ -6,21 +6,33 @@ pub struct Errors {
 }
 
 impl Errors {
-    pub fn error(&mut self, line: usize, message: &str) {
-        self.report(line, "", message);
+    pub fn error(&mut self, line: usize, column: usize, message: &str) {
+        self.report(line, column, "", message);
     }
 
-    pub fn report(&mut self, line: usize, loc: &str, message: &str) {
-        eprintln!("[line {}] Error {}: {}", line, loc, message);
+    pub fn report(&mut self, line: usize, column: usize, loc: &str, message: &str) {
+        eprintln!(
+            "[line {}, column {}] Error {}: {}",
+            line, column, loc, message
+        );
         self.had_error = true;
     }
 
+    /// Reports a problem that doesn't stop the program from running.
+    pub fn warning_token(&self, token: &Token, message: &str) {
+        eprintln!(
+            "[line {}, column {}] Warning at '{}': {}",
+            token.loc.line, token.loc.column, token.loc.lexeme, message
+        );
+    }
+
     pub fn error_token(&mut self, token: &Token, message: &str) {
         if token.r#type == TokenType::Eof {
-            self.report(token.loc.line, " at end", message);
+            self.report(token.loc.line, token.loc.column, " at end", message);
         } else {
             self.report(
                 token.loc.line,
+                token.loc.column,
                 &format!("at '{}'", token.loc.lexeme),
                 message,
             );
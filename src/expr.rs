@@ -1,32 +1,183 @@
+use std::fmt;
+
 use crate::token::{Object, Token};
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     Binary {
         left: Box<Expr>,
         operator: Token,
         right: Box<Expr>,
     },
+    Call {
+        callee: Box<Expr>,
+        paren: Token,
+        arguments: Vec<Expr>,
+    },
+    CompoundAssign {
+        target: Box<Expr>,
+        operator: Token,
+        value: Box<Expr>,
+    },
+    For {
+        keyword: Token,
+        name: Token,
+        iterable: Box<Expr>,
+        body: Box<Expr>,
+    },
     Grouping {
         expr: Box<Expr>,
     },
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    Interpolation {
+        parts: Vec<Expr>,
+    },
+    List {
+        elements: Vec<Expr>,
+    },
     Literal {
         value: Object,
     },
+    Map {
+        brace: Token,
+        entries: Vec<(Expr, Expr)>,
+    },
+    Match {
+        keyword: Token,
+        subject: Box<Expr>,
+        arms: Vec<MatchArm>,
+    },
+    SetIndex {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
+    Throw {
+        keyword: Token,
+        value: Box<Expr>,
+    },
+    Try {
+        body: Box<Expr>,
+        catch: Option<(Token, Box<Expr>)>,
+        finally: Option<Box<Expr>>,
+    },
     Unary {
         operator: Token,
         right: Box<Expr>,
     },
+    Update {
+        target: Box<Expr>,
+        operator: Token,
+        prefix: bool,
+    },
+    Variable {
+        name: Token,
+    },
+}
+
+/// One `pattern if guard => body` arm of a `match` expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    /// A literal, or a negated number literal, compared with `==`.
+    Literal(Expr),
+    /// `a | b`, which matches if any alternative does.
+    Alternatives(Vec<Pattern>),
+    /// A name that matches anything and binds it for the guard and body.
+    Binding(Token),
+    /// `_`, which matches anything.
+    Wildcard,
+}
+
+impl Pattern {
+    /// Whether the pattern matches every value.
+    pub fn is_irrefutable(&self) -> bool {
+        match self {
+            Pattern::Literal(_) => false,
+            Pattern::Alternatives(alternatives) => alternatives.iter().any(Pattern::is_irrefutable),
+            Pattern::Binding(_) | Pattern::Wildcard => true,
+        }
+    }
 }
 
 pub trait Visitor<R, E> {
     fn visit_binary_expr(&self, left: &Expr, operator: &Token, right: &Expr) -> Result<R, E>;
+    fn visit_call_expr(&self, callee: &Expr, paren: &Token, arguments: &[Expr]) -> Result<R, E>;
+    fn visit_compound_assign_expr(
+        &self,
+        target: &Expr,
+        operator: &Token,
+        value: &Expr,
+    ) -> Result<R, E>;
+    fn visit_for_expr(
+        &self,
+        keyword: &Token,
+        name: &Token,
+        iterable: &Expr,
+        body: &Expr,
+    ) -> Result<R, E>;
     fn visit_grouping_expr(&self, expr: &Expr) -> Result<R, E>;
+    fn visit_index_expr(&self, object: &Expr, bracket: &Token, index: &Expr) -> Result<R, E>;
+    fn visit_interpolation_expr(&self, parts: &[Expr]) -> Result<R, E>;
+    fn visit_list_expr(&self, elements: &[Expr]) -> Result<R, E>;
     fn visit_literal_expr(&self, value: &Object) -> Result<R, E>;
+    fn visit_map_expr(&self, brace: &Token, entries: &[(Expr, Expr)]) -> Result<R, E>;
+    fn visit_match_expr(&self, keyword: &Token, subject: &Expr, arms: &[MatchArm]) -> Result<R, E>;
+    fn visit_set_index_expr(
+        &self,
+        object: &Expr,
+        bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<R, E>;
+    fn visit_throw_expr(&self, keyword: &Token, value: &Expr) -> Result<R, E>;
+    fn visit_try_expr(
+        &self,
+        body: &Expr,
+        catch: Option<&(Token, Box<Expr>)>,
+        finally: Option<&Expr>,
+    ) -> Result<R, E>;
     fn visit_unary_expr(&self, operator: &Token, right: &Expr) -> Result<R, E>;
+    fn visit_update_expr(&self, target: &Expr, operator: &Token, prefix: bool) -> Result<R, E>;
+    fn visit_variable_expr(&self, name: &Token) -> Result<R, E>;
 }
 
 impl Expr {
+    /// The line of the token an error in this expression is reported at, if
+    /// the expression has one.
+    pub fn line(&self) -> Option<usize> {
+        let token = match self {
+            Expr::Binary { operator, .. }
+            | Expr::CompoundAssign { operator, .. }
+            | Expr::Unary { operator, .. }
+            | Expr::Update { operator, .. } => operator,
+            Expr::For { keyword, .. }
+            | Expr::Match { keyword, .. }
+            | Expr::Throw { keyword, .. } => keyword,
+            Expr::Call { paren, .. } => paren,
+            Expr::Index { bracket, .. } | Expr::SetIndex { bracket, .. } => bracket,
+            Expr::Map { brace, .. } => brace,
+            Expr::Variable { name } => name,
+            Expr::Grouping { .. }
+            | Expr::Interpolation { .. }
+            | Expr::List { .. }
+            | Expr::Literal { .. }
+            | Expr::Try { .. } => return None,
+        };
+        Some(token.loc.line)
+    }
+
     pub fn accept<R, E>(&self, visitor: &dyn Visitor<R, E>) -> Result<R, E> {
         match self {
             Expr::Binary {
@@ -34,9 +185,56 @@ impl Expr {
                 operator,
                 right,
             } => visitor.visit_binary_expr(left, operator, right),
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+            } => visitor.visit_call_expr(callee, paren, arguments),
+            Expr::CompoundAssign {
+                target,
+                operator,
+                value,
+            } => visitor.visit_compound_assign_expr(target, operator, value),
+            Expr::For {
+                keyword,
+                name,
+                iterable,
+                body,
+            } => visitor.visit_for_expr(keyword, name, iterable, body),
             Expr::Grouping { expr } => visitor.visit_grouping_expr(expr),
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => visitor.visit_index_expr(object, bracket, index),
+            Expr::Interpolation { parts } => visitor.visit_interpolation_expr(parts),
+            Expr::List { elements } => visitor.visit_list_expr(elements),
             Expr::Literal { value } => visitor.visit_literal_expr(value),
+            Expr::Map { brace, entries } => visitor.visit_map_expr(brace, entries),
+            Expr::Match {
+                keyword,
+                subject,
+                arms,
+            } => visitor.visit_match_expr(keyword, subject, arms),
+            Expr::SetIndex {
+                object,
+                bracket,
+                index,
+                value,
+            } => visitor.visit_set_index_expr(object, bracket, index, value),
+            Expr::Throw { keyword, value } => visitor.visit_throw_expr(keyword, value),
+            Expr::Try {
+                body,
+                catch,
+                finally,
+            } => visitor.visit_try_expr(body, catch.as_ref(), finally.as_deref()),
             Expr::Unary { operator, right } => visitor.visit_unary_expr(operator, right),
+            Expr::Update {
+                target,
+                operator,
+                prefix,
+            } => visitor.visit_update_expr(target, operator, *prefix),
+            Expr::Variable { name } => visitor.visit_variable_expr(name),
         }
     }
 }
@@ -44,11 +242,11 @@ impl Expr {
 pub struct AstPrinter;
 
 impl AstPrinter {
-    pub fn print(&self, expr: Expr) -> Result<String, ()> {
-        Ok(expr.accept(self)?)
+    pub fn print(&self, expr: Expr) -> Result<String, fmt::Error> {
+        expr.accept(self)
     }
 
-    fn parenthesize(&self, name: String, exprs: &[&Expr]) -> Result<String, ()> {
+    fn parenthesize(&self, name: String, exprs: &[&Expr]) -> Result<String, fmt::Error> {
         let mut r = String::new();
         r.push('(');
         r.push_str(&name);
@@ -59,24 +257,172 @@ impl AstPrinter {
         r.push(')');
         Ok(r)
     }
+
+    fn pattern(&self, pattern: &Pattern) -> Result<String, fmt::Error> {
+        match pattern {
+            Pattern::Literal(expr) => expr.accept(self),
+            Pattern::Alternatives(alternatives) => {
+                let alternatives = alternatives
+                    .iter()
+                    .map(|alternative| self.pattern(alternative))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(alternatives.join(" | "))
+            }
+            Pattern::Binding(name) => Ok(name.loc.lexeme.clone()),
+            Pattern::Wildcard => Ok("_".to_string()),
+        }
+    }
 }
 
-impl Visitor<String, ()> for AstPrinter {
-    fn visit_binary_expr(&self, left: &Expr, operator: &Token, right: &Expr) -> Result<String, ()> {
+impl Visitor<String, fmt::Error> for AstPrinter {
+    fn visit_binary_expr(
+        &self,
+        left: &Expr,
+        operator: &Token,
+        right: &Expr,
+    ) -> Result<String, fmt::Error> {
         self.parenthesize(operator.loc.lexeme.clone(), &[left, right])
     }
 
-    fn visit_grouping_expr(&self, expr: &Expr) -> Result<String, ()> {
+    fn visit_call_expr(
+        &self,
+        callee: &Expr,
+        _paren: &Token,
+        arguments: &[Expr],
+    ) -> Result<String, fmt::Error> {
+        let exprs: Vec<&Expr> = std::iter::once(callee).chain(arguments).collect();
+        self.parenthesize("call".to_string(), &exprs)
+    }
+
+    fn visit_compound_assign_expr(
+        &self,
+        target: &Expr,
+        operator: &Token,
+        value: &Expr,
+    ) -> Result<String, fmt::Error> {
+        self.parenthesize(operator.loc.lexeme.clone(), &[target, value])
+    }
+
+    fn visit_for_expr(
+        &self,
+        _keyword: &Token,
+        name: &Token,
+        iterable: &Expr,
+        body: &Expr,
+    ) -> Result<String, fmt::Error> {
+        self.parenthesize(format!("for {}", name.loc.lexeme), &[iterable, body])
+    }
+
+    fn visit_grouping_expr(&self, expr: &Expr) -> Result<String, fmt::Error> {
         self.parenthesize("group".to_string(), &[expr])
     }
 
-    fn visit_literal_expr(&self, value: &Object) -> Result<String, ()> {
+    fn visit_index_expr(
+        &self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+    ) -> Result<String, fmt::Error> {
+        self.parenthesize("index".to_string(), &[object, index])
+    }
+
+    fn visit_interpolation_expr(&self, parts: &[Expr]) -> Result<String, fmt::Error> {
+        let parts: Vec<&Expr> = parts.iter().collect();
+        self.parenthesize("interpolate".to_string(), &parts)
+    }
+
+    fn visit_list_expr(&self, elements: &[Expr]) -> Result<String, fmt::Error> {
+        let elements: Vec<&Expr> = elements.iter().collect();
+        self.parenthesize("list".to_string(), &elements)
+    }
+
+    fn visit_literal_expr(&self, value: &Object) -> Result<String, fmt::Error> {
         Ok(value.to_string())
     }
 
-    fn visit_unary_expr(&self, operator: &Token, right: &Expr) -> Result<String, ()> {
+    fn visit_map_expr(
+        &self,
+        _brace: &Token,
+        entries: &[(Expr, Expr)],
+    ) -> Result<String, fmt::Error> {
+        let entries: Vec<&Expr> = entries.iter().flat_map(|(k, v)| [k, v]).collect();
+        self.parenthesize("map".to_string(), &entries)
+    }
+
+    fn visit_match_expr(
+        &self,
+        _keyword: &Token,
+        subject: &Expr,
+        arms: &[MatchArm],
+    ) -> Result<String, fmt::Error> {
+        let mut r = format!("(match {}", subject.accept(self)?);
+        for arm in arms {
+            r.push_str(&format!(" ({}", self.pattern(&arm.pattern)?));
+            if let Some(guard) = &arm.guard {
+                r.push_str(&format!(" if {}", guard.accept(self)?));
+            }
+            r.push_str(&format!(" {})", arm.body.accept(self)?));
+        }
+        r.push(')');
+        Ok(r)
+    }
+
+    fn visit_set_index_expr(
+        &self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<String, fmt::Error> {
+        self.parenthesize("set-index".to_string(), &[object, index, value])
+    }
+
+    fn visit_throw_expr(&self, _keyword: &Token, value: &Expr) -> Result<String, fmt::Error> {
+        self.parenthesize("throw".to_string(), &[value])
+    }
+
+    fn visit_try_expr(
+        &self,
+        body: &Expr,
+        catch: Option<&(Token, Box<Expr>)>,
+        finally: Option<&Expr>,
+    ) -> Result<String, fmt::Error> {
+        let mut r = format!("(try {}", body.accept(self)?);
+        if let Some((name, handler)) = catch {
+            r.push_str(&format!(
+                " (catch {} {})",
+                name.loc.lexeme,
+                handler.accept(self)?
+            ));
+        }
+        if let Some(finally) = finally {
+            r.push_str(&format!(" (finally {})", finally.accept(self)?));
+        }
+        r.push(')');
+        Ok(r)
+    }
+
+    fn visit_unary_expr(&self, operator: &Token, right: &Expr) -> Result<String, fmt::Error> {
         self.parenthesize(operator.loc.lexeme.clone(), &[right])
     }
+
+    fn visit_update_expr(
+        &self,
+        target: &Expr,
+        operator: &Token,
+        prefix: bool,
+    ) -> Result<String, fmt::Error> {
+        let name = if prefix {
+            operator.loc.lexeme.clone()
+        } else {
+            format!("post{}", operator.loc.lexeme)
+        };
+        self.parenthesize(name, &[target])
+    }
+
+    fn visit_variable_expr(&self, name: &Token) -> Result<String, fmt::Error> {
+        Ok(name.loc.lexeme.clone())
+    }
 }
 
 #[cfg(test)]
@@ -94,11 +440,12 @@ mod tests {
                     literal: None,
                     loc: Location {
                         line: 1,
+                        column: 1,
                         lexeme: "-".to_string(),
                     },
                 },
                 right: Box::new(Expr::Literal {
-                    value: Object::Number(123.0),
+                    value: Object::Int(123),
                 }),
             }),
             operator: Token {
@@ -107,6 +454,7 @@ mod tests {
                 loc: Location {
                     lexeme: "*".to_string(),
                     line: 1,
+                    column: 7,
                 },
             },
             right: Box::new(Expr::Grouping {
@@ -1,10 +1,45 @@
+use std::{
+    cell::RefCell,
+    cmp::Ordering::{self, Equal, Greater, Less},
+    rc::Rc,
+};
+
+use num_bigint::{BigInt, BigUint};
+use num_traits::{FromPrimitive, One, Signed, ToPrimitive, Zero};
+use rust_decimal::Decimal;
+
 use crate::{
-    expr::{Expr, Visitor},
+    environment::Environment,
+    expr::{Expr, MatchArm, Pattern, Visitor},
+    list::List,
+    map::Map,
+    native::NATIVES,
     token::{Object, Token, TokenType},
     InterpreterError,
 };
 
-pub struct Interpreter;
+/// The most bits a bigint built by `**` or `<<` may have, so that a huge
+/// exponent or shift count is an error rather than exhausting memory.
+const MAX_BIGINT_BITS: u64 = 1 << 20;
+
+/// The most digits a decimal may have after its point.
+const MAX_DECIMAL_SCALE: u32 = 28;
+
+pub struct Interpreter {
+    environment: RefCell<Rc<RefCell<Environment>>>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        let mut globals = Environment::default();
+        for native in NATIVES {
+            globals.define(native.name, Object::Native(*native));
+        }
+        Self {
+            environment: RefCell::new(Rc::new(RefCell::new(globals))),
+        }
+    }
+}
 
 impl Interpreter {
     pub fn interpret(&mut self, expr: &Expr) -> Result<(), InterpreterError> {
@@ -14,82 +49,867 @@ impl Interpreter {
     }
 
     fn evaluate(&self, expr: &Expr) -> Result<Object, InterpreterError> {
-        expr.accept(self)
+        expr.accept(self).map_err(|error| match expr.line() {
+            Some(line) => error.at(line),
+            None => error,
+        })
     }
-}
 
-impl Visitor<Object, InterpreterError> for Interpreter {
-    fn visit_binary_expr(
+    /// The values a `for` loop over `iterable` visits: a list's elements, a
+    /// map's keys or a string's characters. Lists and maps are copied first,
+    /// so changing them inside the loop doesn't change what it visits.
+    fn iterate(
+        &self,
+        iterable: Object,
+    ) -> Result<Box<dyn Iterator<Item = Object>>, InterpreterError> {
+        match iterable {
+            Object::List(list) => Ok(Box::new(list.borrow().to_vec().into_iter())),
+            Object::Map(map) => {
+                let keys: Vec<Object> = map.borrow().iter().map(|(key, _)| key.clone()).collect();
+                Ok(Box::new(keys.into_iter()))
+            }
+            Object::String(string) => {
+                let chars: Vec<Object> = string
+                    .chars()
+                    .map(|c| Object::String(c.to_string()))
+                    .collect();
+                Ok(Box::new(chars.into_iter()))
+            }
+            _ => Err(InterpreterError::NotIterable(iterable)),
+        }
+    }
+
+    /// Runs `f` with `environment` as the current scope, restoring the
+    /// previous scope afterwards even if `f` fails.
+    fn with_environment<T>(
+        &self,
+        environment: Environment,
+        f: impl FnOnce() -> Result<T, InterpreterError>,
+    ) -> Result<T, InterpreterError> {
+        let previous = self.environment.replace(Rc::new(RefCell::new(environment)));
+        let result = f();
+        self.environment.replace(previous);
+        result
+    }
+
+    /// Checks `value` against `pattern`, defining any name it binds in
+    /// `scope`.
+    fn matches(
+        &self,
+        pattern: &Pattern,
+        value: &Object,
+        scope: &mut Environment,
+    ) -> Result<bool, InterpreterError> {
+        match pattern {
+            Pattern::Literal(literal) => Ok(self.evaluate(literal)? == *value),
+            Pattern::Alternatives(alternatives) => {
+                for alternative in alternatives {
+                    if self.matches(alternative, value, scope)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Pattern::Binding(name) => {
+                scope.define(&name.loc.lexeme, value.clone());
+                Ok(true)
+            }
+            Pattern::Wildcard => Ok(true),
+        }
+    }
+
+    /// The value a `catch` clause receives for `error`: the thrown value
+    /// itself, or a map describing a runtime error.
+    fn error_object(&self, error: &InterpreterError) -> Object {
+        if let InterpreterError::Thrown(_, value) = error {
+            return value.clone();
+        }
+
+        let mut map = Map::default();
+        let (message, line) = match error {
+            InterpreterError::Located(line, error) => (error.to_string(), Some(*line)),
+            _ => (error.to_string(), None),
+        };
+        map.insert(
+            Object::String("message".to_string()),
+            Object::String(message),
+        );
+        if let Some(line) = line {
+            map.insert(Object::String("line".to_string()), Object::Int(line as i64));
+        }
+        Object::Map(Rc::new(RefCell::new(map)))
+    }
+
+    fn binary(
         &self,
-        left: &Expr,
         operator: &Token,
-        right: &Expr,
+        left: Object,
+        right: Object,
     ) -> Result<Object, InterpreterError> {
-        let left = self.evaluate(left)?;
-        let right = self.evaluate(right)?;
-
         match operator.r#type {
-            TokenType::Minus => match (&left, &right) {
-                (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l - r)),
-                _ => Err(InterpreterError::InvalidNumberOperands(left, right)),
-            },
-            TokenType::Slash => match (&left, &right) {
-                (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l / r)),
-                _ => Err(InterpreterError::InvalidNumberOperands(left, right)),
-            },
-            TokenType::Star => match (&left, &right) {
-                (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l * r)),
-                _ => Err(InterpreterError::InvalidNumberOperands(left, right)),
-            },
+            TokenType::Minus | TokenType::Slash | TokenType::Star | TokenType::Percent => {
+                self.arithmetic(operator, left, right)
+            }
+            TokenType::StarStar => self.power(left, right),
+            TokenType::Ampersand
+            | TokenType::Pipe
+            | TokenType::Caret
+            | TokenType::LessLess
+            | TokenType::GreaterGreater => self.bitwise(operator, left, right),
             TokenType::Plus => match (&left, &right) {
-                (Object::Number(l), Object::Number(r)) => Ok(Object::Number(l + r)),
                 (Object::String(l), Object::String(r)) => Ok(Object::String(l.clone() + r)),
+                _ if left.as_f64().is_some() && right.as_f64().is_some() => {
+                    self.arithmetic(operator, left, right)
+                }
                 _ => Err(InterpreterError::InvalidStringOrNumberOperands(left, right)),
             },
-            TokenType::Greater => match (&left, &right) {
-                (Object::Number(l), Object::Number(r)) => Ok(Object::Bool(l > r)),
-                _ => Err(InterpreterError::InvalidNumberOperands(left, right)),
-            },
-            TokenType::GreaterEqual => match (&left, &right) {
-                (Object::Number(l), Object::Number(r)) => Ok(Object::Bool(l >= r)),
-                _ => Err(InterpreterError::InvalidNumberOperands(left, right)),
-            },
-            TokenType::Less => match (&left, &right) {
-                (Object::Number(l), Object::Number(r)) => Ok(Object::Bool(l < r)),
-                _ => Err(InterpreterError::InvalidNumberOperands(left, right)),
-            },
-            TokenType::LessEqual => match (&left, &right) {
-                (Object::Number(l), Object::Number(r)) => Ok(Object::Bool(l <= r)),
-                _ => Err(InterpreterError::InvalidNumberOperands(left, right)),
-            },
+            TokenType::Greater => Ok(Object::Bool(self.compare(left, right)? == Some(Greater))),
+            TokenType::GreaterEqual => Ok(Object::Bool(matches!(
+                self.compare(left, right)?,
+                Some(Greater | Equal)
+            ))),
+            TokenType::Less => Ok(Object::Bool(self.compare(left, right)? == Some(Less))),
+            TokenType::LessEqual => Ok(Object::Bool(matches!(
+                self.compare(left, right)?,
+                Some(Less | Equal)
+            ))),
             TokenType::BangEqual => Ok(Object::Bool(left != right)),
             TokenType::EqualEqual => Ok(Object::Bool(left == right)),
+            TokenType::Comma => Ok(right),
+            _ => Err(InterpreterError::InvalidOperatorError(
+                left,
+                Box::new(operator.clone()),
+                right,
+            )),
+        }
+    }
+
+    /// Reads an assignment target, computes its new value with `update` and
+    /// writes it back, returning the old and new values. The target's
+    /// subexpressions are evaluated only once.
+    fn update_target(
+        &self,
+        target: &Expr,
+        update: impl FnOnce(Object) -> Result<Object, InterpreterError>,
+    ) -> Result<(Object, Object), InterpreterError> {
+        match target {
+            Expr::Index { object, index, .. } => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                self.check_mutable(&object)?;
+                let old = self.get_index(object.clone(), index.clone())?;
+                let new = update(old.clone())?;
+                self.set_index(object, index, new.clone())?;
+                Ok((old, new))
+            }
+            _ => unreachable!("the parser only builds updates of index targets"),
+        }
+    }
+
+    fn get_index(&self, object: Object, index: Object) -> Result<Object, InterpreterError> {
+        match object {
+            Object::List(list) => {
+                let list = list.borrow();
+                let i = self.list_index(&index, list.len())?;
+                Ok(list[i].clone())
+            }
+            Object::Map(map) => {
+                let key = self.map_key(index)?;
+                map.borrow()
+                    .get(&key)
+                    .cloned()
+                    .ok_or(InterpreterError::MissingKey(key))
+            }
+            _ => Err(InterpreterError::NotIndexable(object)),
+        }
+    }
+
+    fn set_index(
+        &self,
+        object: Object,
+        index: Object,
+        value: Object,
+    ) -> Result<Object, InterpreterError> {
+        self.check_mutable(&object)?;
+        match object {
+            Object::List(list) => {
+                let mut list = list.borrow_mut();
+                let i = self.list_index(&index, list.len())?;
+                list[i] = value.clone();
+                Ok(value)
+            }
+            Object::Map(map) => {
+                let key = self.map_key(index)?;
+                map.borrow_mut().insert(key, value.clone());
+                Ok(value)
+            }
+            _ => Err(InterpreterError::NotIndexable(object)),
+        }
+    }
+
+    /// Checks that `object` may be written to, which a frozen list or map
+    /// may not.
+    pub(crate) fn check_mutable(&self, object: &Object) -> Result<(), InterpreterError> {
+        match object {
+            Object::List(list) if list.borrow().is_frozen() => {
+                Err(InterpreterError::Frozen("list"))
+            }
+            Object::Map(map) if map.borrow().is_frozen() => Err(InterpreterError::Frozen("map")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that `index` is a whole number within a list of length `len`.
+    /// Bigints are accepted too, since arithmetic can leave a small value
+    /// as a bigint.
+    pub(crate) fn list_index(&self, index: &Object, len: usize) -> Result<usize, InterpreterError> {
+        match index.as_bigint() {
+            Some(int) if !int.is_negative() => int
+                .to_usize()
+                .filter(|i| *i < len)
+                .ok_or(InterpreterError::IndexOutOfBounds(index.clone(), len)),
+            _ => Err(InterpreterError::InvalidIndex(index.clone())),
+        }
+    }
+
+    /// Applies an arithmetic operator to two numbers.
+    ///
+    /// Two ints stay ints: `/` truncates toward zero and `%` takes the sign
+    /// of the dividend. An int result that overflows is promoted to a bigint,
+    /// and a bigint with an int stays a bigint. A decimal with an int or
+    /// bigint is exact except for `/`, which rounds to 28 significant digits;
+    /// a result that can't be stored exactly is an error, as is mixing a
+    /// decimal with a float, rather than a silent loss of precision.
+    /// Otherwise, if either operand is a float, both are promoted to floats.
+    fn arithmetic(
+        &self,
+        operator: &Token,
+        left: Object,
+        right: Object,
+    ) -> Result<Object, InterpreterError> {
+        if let (Object::Int(l), Object::Int(r)) = (&left, &right) {
+            let (l, r) = (*l, *r);
+            let result = match operator.r#type {
+                TokenType::Plus => l.checked_add(r),
+                TokenType::Minus => l.checked_sub(r),
+                TokenType::Star => l.checked_mul(r),
+                TokenType::Slash | TokenType::Percent if r == 0 => {
+                    return Err(InterpreterError::DivisionByZero)
+                }
+                TokenType::Slash => l.checked_div(r),
+                TokenType::Percent => l.checked_rem(r),
+                _ => None,
+            };
+            if let Some(result) = result {
+                return Ok(Object::Int(result));
+            }
+        }
+
+        if matches!(left, Object::Decimal(_)) || matches!(right, Object::Decimal(_)) {
+            return self.decimal_arithmetic(operator, left, right);
+        }
+
+        if let (Some(l), Some(r)) = (left.as_bigint(), right.as_bigint()) {
+            return match operator.r#type {
+                TokenType::Plus => Ok(Object::BigInt(l + r)),
+                TokenType::Minus => Ok(Object::BigInt(l - r)),
+                TokenType::Star => Ok(Object::BigInt(l * r)),
+                TokenType::Slash | TokenType::Percent if r.is_zero() => {
+                    Err(InterpreterError::DivisionByZero)
+                }
+                TokenType::Slash => Ok(Object::BigInt(l / r)),
+                TokenType::Percent => Ok(Object::BigInt(l % r)),
+                _ => Err(InterpreterError::InvalidOperatorError(
+                    left,
+                    Box::new(operator.clone()),
+                    right,
+                )),
+            };
+        }
+
+        let (Some(l), Some(r)) = (left.as_f64(), right.as_f64()) else {
+            return Err(InterpreterError::InvalidNumberOperands(left, right));
+        };
+        match operator.r#type {
+            TokenType::Plus => Ok(Object::Number(l + r)),
+            TokenType::Minus => Ok(Object::Number(l - r)),
+            TokenType::Star => Ok(Object::Number(l * r)),
+            TokenType::Slash => Ok(Object::Number(l / r)),
+            TokenType::Percent => Ok(Object::Number(l % r)),
+            _ => Err(InterpreterError::InvalidOperatorError(
+                left,
+                Box::new(operator.clone()),
+                right,
+            )),
+        }
+    }
+
+    fn decimal_arithmetic(
+        &self,
+        operator: &Token,
+        left: Object,
+        right: Object,
+    ) -> Result<Object, InterpreterError> {
+        let (Some(l), Some(r)) = (left.as_decimal(), right.as_decimal()) else {
+            return match (&left, &right) {
+                (Object::Number(_), _) | (_, Object::Number(_)) => {
+                    Err(InterpreterError::MixedDecimalAndFloat(left, right))
+                }
+                // A bigint too large to be a decimal.
+                (Object::BigInt(_), _) | (_, Object::BigInt(_)) => {
+                    Err(InterpreterError::DecimalOverflow)
+                }
+                _ => Err(InterpreterError::InvalidNumberOperands(left, right)),
+            };
+        };
+        let result = match operator.r#type {
+            TokenType::Plus => return decimal_sum(l, r).map(Object::Decimal),
+            TokenType::Minus => return decimal_sum(l, -r).map(Object::Decimal),
+            TokenType::Star => return decimal_product(l, r).map(Object::Decimal),
+            TokenType::Slash | TokenType::Percent if r.is_zero() => {
+                return Err(InterpreterError::DivisionByZero)
+            }
+            TokenType::Slash => return decimal_quotient(l, r).map(Object::Decimal),
+            TokenType::Percent => l.checked_rem(r),
+            _ => {
+                return Err(InterpreterError::InvalidOperatorError(
+                    left,
+                    Box::new(operator.clone()),
+                    right,
+                ))
+            }
+        };
+        result
+            .map(Object::Decimal)
+            .ok_or(InterpreterError::DecimalOverflow)
+    }
+
+    /// Raises `left` to the power `right`.
+    ///
+    /// An int or bigint to a non-negative int power is exact, promoting to a
+    /// bigint on overflow. A decimal to an int power is a decimal. Anything
+    /// else is computed with floats.
+    fn power(&self, left: Object, right: Object) -> Result<Object, InterpreterError> {
+        if left.as_f64().is_none() || right.as_f64().is_none() {
+            return Err(InterpreterError::InvalidNumberOperands(left, right));
+        }
+
+        match (&left, &right) {
+            (Object::Decimal(_), Object::Number(_)) | (Object::Number(_), Object::Decimal(_)) => {
+                Err(InterpreterError::MixedDecimalAndFloat(left, right))
+            }
+            (Object::Decimal(_), _) | (_, Object::Decimal(_)) => {
+                let base = left.as_decimal().ok_or(InterpreterError::DecimalOverflow)?;
+                let exp = right
+                    .as_decimal()
+                    .ok_or(InterpreterError::DecimalOverflow)?;
+                if !exp.fract().is_zero() {
+                    return Err(InterpreterError::FractionalDecimalExponent(right));
+                }
+                let exp = exp
+                    .to_i64()
+                    .ok_or(InterpreterError::ExponentTooLarge(right.clone()))?;
+                self.decimal_power(base, exp)
+            }
+            (Object::Int(_) | Object::BigInt(_), Object::Int(_) | Object::BigInt(_))
+                if !right.as_bigint().unwrap().is_negative() =>
+            {
+                let base = left.as_bigint().unwrap();
+                // 0, 1 and -1 stay small however large the exponent is.
+                if base.magnitude() <= &BigUint::one() {
+                    let exp = right.as_bigint().unwrap();
+                    let result = match base.to_i64().unwrap() {
+                        0 if !exp.is_zero() => 0,
+                        -1 if exp.bit(0) => -1,
+                        _ => 1,
+                    };
+                    return Ok(Object::Int(result));
+                }
+                let exp = right
+                    .as_bigint()
+                    .unwrap()
+                    .to_u32()
+                    .filter(|exp| {
+                        base.bits().saturating_sub(1) * u64::from(*exp) <= MAX_BIGINT_BITS
+                    })
+                    .ok_or(InterpreterError::ExponentTooLarge(right.clone()))?;
+                if let Object::Int(base) = left {
+                    if let Some(result) = base.checked_pow(exp) {
+                        return Ok(Object::Int(result));
+                    }
+                }
+                Ok(Object::BigInt(base.pow(exp)))
+            }
+            _ => Ok(Object::Number(
+                left.as_f64().unwrap().powf(right.as_f64().unwrap()),
+            )),
+        }
+    }
+
+    /// Raises a decimal to a whole power. A positive power is exact,
+    /// failing rather than rounding. A negative power divides 1 by the
+    /// positive one, and rounds like `/`.
+    fn decimal_power(&self, base: Decimal, exp: i64) -> Result<Object, InterpreterError> {
+        let power = decimal_unsigned_power(base, exp.unsigned_abs());
+        if exp >= 0 {
+            return power.map(Object::Decimal);
+        }
+
+        // The reciprocal of a power too large to store is too small to
+        // store, and the other way round.
+        let power = power.map_err(|error| match error {
+            InterpreterError::DecimalOverflow => InterpreterError::DecimalPrecisionLoss,
+            InterpreterError::DecimalPrecisionLoss => InterpreterError::DecimalOverflow,
+            error => error,
+        })?;
+        if power.is_zero() {
+            return Err(InterpreterError::DivisionByZero);
+        }
+        decimal_quotient(Decimal::ONE, power).map(Object::Decimal)
+    }
+
+    /// Applies a bitwise operator to two ints or bigints. Negative numbers
+    /// behave as infinite two's complement, so `>>` rounds toward negative
+    /// infinity, and a `<<` that overflows an int promotes to a bigint.
+    fn bitwise(
+        &self,
+        operator: &Token,
+        left: Object,
+        right: Object,
+    ) -> Result<Object, InterpreterError> {
+        if matches!(
+            operator.r#type,
+            TokenType::LessLess | TokenType::GreaterGreater
+        ) {
+            return self.shift(operator, left, right);
+        }
+
+        if let (Object::Int(l), Object::Int(r)) = (&left, &right) {
+            return match operator.r#type {
+                TokenType::Ampersand => Ok(Object::Int(l & r)),
+                TokenType::Pipe => Ok(Object::Int(l | r)),
+                TokenType::Caret => Ok(Object::Int(l ^ r)),
+                _ => Err(InterpreterError::InvalidOperatorError(
+                    left,
+                    Box::new(operator.clone()),
+                    right,
+                )),
+            };
+        }
+
+        let (Some(l), Some(r)) = (left.as_bigint(), right.as_bigint()) else {
+            return Err(InterpreterError::InvalidIntegerOperands(left, right));
+        };
+        match operator.r#type {
+            TokenType::Ampersand => Ok(Object::BigInt(l & r)),
+            TokenType::Pipe => Ok(Object::BigInt(l | r)),
+            TokenType::Caret => Ok(Object::BigInt(l ^ r)),
             _ => Err(InterpreterError::InvalidOperatorError(
                 left,
-                operator.clone(),
+                Box::new(operator.clone()),
                 right,
             )),
         }
     }
 
+    fn shift(
+        &self,
+        operator: &Token,
+        left: Object,
+        right: Object,
+    ) -> Result<Object, InterpreterError> {
+        let count = match right.as_bigint() {
+            Some(count) if count.is_negative() => {
+                return Err(InterpreterError::NegativeShiftCount(right))
+            }
+            // Shifting right by more bits than any value has gives 0 or -1
+            // however far it goes.
+            Some(count) => count.to_u64().unwrap_or(u64::MAX),
+            None => return Err(InterpreterError::InvalidIntegerOperands(left, right)),
+        };
+
+        match (&left, &operator.r#type) {
+            (Object::Int(l), TokenType::GreaterGreater) => Ok(Object::Int(l >> count.min(63))),
+            (Object::BigInt(big), TokenType::GreaterGreater) => {
+                Ok(Object::BigInt(big >> count.min(big.bits())))
+            }
+            (Object::Int(l), _) if count < 64 && (l << count) >> count == *l => {
+                Ok(Object::Int(l << count))
+            }
+            (Object::Int(_) | Object::BigInt(_), _) => {
+                let big = left.as_bigint().unwrap();
+                if big.is_zero() {
+                    return Ok(Object::Int(0));
+                }
+                if big.bits().saturating_add(count) > MAX_BIGINT_BITS {
+                    return Err(InterpreterError::ShiftTooLarge(right));
+                }
+                Ok(Object::BigInt(big << count))
+            }
+            _ => Err(InterpreterError::InvalidIntegerOperands(left, right)),
+        }
+    }
+
+    /// Orders two numbers, or returns `None` if either is NaN. Like
+    /// arithmetic, ordering a decimal against a float is an error.
+    fn compare(&self, left: Object, right: Object) -> Result<Option<Ordering>, InterpreterError> {
+        if let (Object::Int(l), Object::Int(r)) = (&left, &right) {
+            return Ok(Some(l.cmp(r)));
+        }
+        if let (Some(l), Some(r)) = (left.as_bigint(), right.as_bigint()) {
+            return Ok(Some(l.cmp(&r)));
+        }
+        if let (Some(l), Some(r)) = (left.as_decimal(), right.as_decimal()) {
+            return Ok(Some(l.cmp(&r)));
+        }
+        match (&left, &right) {
+            (Object::Decimal(_), Object::Number(_)) | (Object::Number(_), Object::Decimal(_)) => {
+                return Err(InterpreterError::MixedDecimalAndFloat(left, right));
+            }
+            (Object::Int(_) | Object::BigInt(_), Object::Number(r)) => {
+                return Ok(compare_int_float(&left.as_bigint().unwrap(), *r));
+            }
+            (Object::Number(l), Object::Int(_) | Object::BigInt(_)) => {
+                return Ok(
+                    compare_int_float(&right.as_bigint().unwrap(), *l).map(Ordering::reverse)
+                );
+            }
+            _ => {}
+        }
+        match (left.as_f64(), right.as_f64()) {
+            (Some(l), Some(r)) => Ok(l.partial_cmp(&r)),
+            _ => Err(InterpreterError::InvalidNumberOperands(left, right)),
+        }
+    }
+
+    pub(crate) fn map_key(&self, key: Object) -> Result<Object, InterpreterError> {
+        if key.is_hashable() {
+            Ok(key)
+        } else {
+            Err(InterpreterError::UnhashableKey(key))
+        }
+    }
+}
+
+/// The mantissa of `dec` when it is written with `scale` decimal places,
+/// which must be at least its own.
+fn mantissa_at(dec: Decimal, scale: u32) -> BigInt {
+    BigInt::from(dec.mantissa()) * BigInt::from(10).pow(scale - dec.scale())
+}
+
+/// The decimal `mantissa / 10^scale`. Trailing zeros are dropped only if
+/// the value doesn't fit otherwise, and a value that still doesn't fit is
+/// an error rather than being rounded.
+pub(crate) fn exact_decimal(
+    mut mantissa: BigInt,
+    mut scale: u32,
+) -> Result<Decimal, InterpreterError> {
+    let ten = BigInt::from(10);
+    loop {
+        if scale <= MAX_DECIMAL_SCALE {
+            if let Some(dec) = mantissa
+                .to_i128()
+                .and_then(|mantissa| Decimal::try_from_i128_with_scale(mantissa, scale).ok())
+            {
+                return Ok(dec);
+            }
+        }
+        if scale == 0 || !(&mantissa % &ten).is_zero() {
+            break;
+        }
+        mantissa /= &ten;
+        scale -= 1;
+    }
+    // Digits after the point can't be dropped without rounding, but a whole
+    // part that is too large can't be stored at all.
+    if (mantissa / ten.pow(scale)).bits() <= 96 {
+        Err(InterpreterError::DecimalPrecisionLoss)
+    } else {
+        Err(InterpreterError::DecimalOverflow)
+    }
+}
+
+/// `l + r`, computed exactly.
+fn decimal_sum(l: Decimal, r: Decimal) -> Result<Decimal, InterpreterError> {
+    let scale = l.scale().max(r.scale());
+    exact_decimal(mantissa_at(l, scale) + mantissa_at(r, scale), scale)
+}
+
+/// `l * r`, computed exactly.
+fn decimal_product(l: Decimal, r: Decimal) -> Result<Decimal, InterpreterError> {
+    exact_decimal(
+        BigInt::from(l.mantissa()) * BigInt::from(r.mantissa()),
+        l.scale() + r.scale(),
+    )
+}
+
+/// `base ** exp`, computed exactly by repeated squaring.
+fn decimal_unsigned_power(base: Decimal, mut exp: u64) -> Result<Decimal, InterpreterError> {
+    let mut result = Decimal::ONE;
+    let mut square = base;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = decimal_product(result, square)?;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            square = decimal_product(square, square)?;
+        }
+    }
+    Ok(result)
+}
+
+/// `l / r` rounded to 28 significant digits. A quotient so small that it
+/// rounds to zero has lost all its precision, so it is an error.
+fn decimal_quotient(l: Decimal, r: Decimal) -> Result<Decimal, InterpreterError> {
+    match l.checked_div(r) {
+        Some(quotient) if quotient.is_zero() && !l.is_zero() => {
+            Err(InterpreterError::DecimalPrecisionLoss)
+        }
+        Some(quotient) => Ok(quotient),
+        None => Err(InterpreterError::DecimalOverflow),
+    }
+}
+
+/// Orders an int against a float exactly, rather than rounding the int to
+/// a float first, so that it agrees with `==`. Returns `None` if the float
+/// is NaN.
+fn compare_int_float(int: &BigInt, float: f64) -> Option<Ordering> {
+    if float.is_nan() {
+        return None;
+    }
+    if float.is_infinite() {
+        return Some(if float > 0.0 { Less } else { Greater });
+    }
+    let whole = float.trunc();
+    let fract = float - whole;
+    Some(
+        int.cmp(&BigInt::from_f64(whole).unwrap())
+            .then(0.0.partial_cmp(&fract).unwrap()),
+    )
+}
+
+impl Visitor<Object, InterpreterError> for Interpreter {
+    fn visit_binary_expr(
+        &self,
+        left: &Expr,
+        operator: &Token,
+        right: &Expr,
+    ) -> Result<Object, InterpreterError> {
+        let left = self.evaluate(left)?;
+        let right = self.evaluate(right)?;
+        self.binary(operator, left, right)
+    }
+
+    fn visit_call_expr(
+        &self,
+        callee: &Expr,
+        _paren: &Token,
+        arguments: &[Expr],
+    ) -> Result<Object, InterpreterError> {
+        let callee = self.evaluate(callee)?;
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.evaluate(argument))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let Object::Native(native) = callee else {
+            return Err(InterpreterError::NotCallable(callee));
+        };
+        if arguments.len() != native.arity {
+            return Err(InterpreterError::ArityMismatch(
+                native.arity,
+                arguments.len(),
+            ));
+        }
+        (native.function)(self, &arguments)
+    }
+
+    fn visit_compound_assign_expr(
+        &self,
+        target: &Expr,
+        operator: &Token,
+        value: &Expr,
+    ) -> Result<Object, InterpreterError> {
+        let operator = operator.compound_operator();
+        let (_, new) = self.update_target(target, |old| {
+            let value = self.evaluate(value)?;
+            self.binary(&operator, old, value)
+        })?;
+        Ok(new)
+    }
+
+    fn visit_for_expr(
+        &self,
+        _keyword: &Token,
+        name: &Token,
+        iterable: &Expr,
+        body: &Expr,
+    ) -> Result<Object, InterpreterError> {
+        let iterable = self.evaluate(iterable)?;
+        for value in self.iterate(iterable)? {
+            let mut scope = Environment::new(self.environment.borrow().clone());
+            scope.define(&name.loc.lexeme, value);
+            self.with_environment(scope, || self.evaluate(body))?;
+        }
+        Ok(Object::Nil)
+    }
+
     fn visit_grouping_expr(&self, expr: &Expr) -> Result<Object, InterpreterError> {
         self.evaluate(expr)
     }
 
+    fn visit_index_expr(
+        &self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+    ) -> Result<Object, InterpreterError> {
+        let object = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+        self.get_index(object, index)
+    }
+
+    fn visit_interpolation_expr(&self, parts: &[Expr]) -> Result<Object, InterpreterError> {
+        let mut result = String::new();
+        for part in parts {
+            result.push_str(&self.evaluate(part)?.to_string());
+        }
+        Ok(Object::String(result))
+    }
+
+    fn visit_list_expr(&self, elements: &[Expr]) -> Result<Object, InterpreterError> {
+        let elements = elements
+            .iter()
+            .map(|element| self.evaluate(element))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Object::List(Rc::new(RefCell::new(List::from(elements)))))
+    }
+
     fn visit_literal_expr(&self, value: &Object) -> Result<Object, InterpreterError> {
         Ok(value.clone())
     }
 
+    fn visit_map_expr(
+        &self,
+        _brace: &Token,
+        entries: &[(Expr, Expr)],
+    ) -> Result<Object, InterpreterError> {
+        let mut map = Map::default();
+        for (key, value) in entries {
+            let key = self.map_key(self.evaluate(key)?)?;
+            let value = self.evaluate(value)?;
+            map.insert(key, value);
+        }
+        Ok(Object::Map(Rc::new(RefCell::new(map))))
+    }
+
+    fn visit_match_expr(
+        &self,
+        _keyword: &Token,
+        subject: &Expr,
+        arms: &[MatchArm],
+    ) -> Result<Object, InterpreterError> {
+        let value = self.evaluate(subject)?;
+
+        for arm in arms {
+            let mut scope = Environment::new(self.environment.borrow().clone());
+            if !self.matches(&arm.pattern, &value, &mut scope)? {
+                continue;
+            }
+            let result = self.with_environment(scope, || {
+                if let Some(guard) = &arm.guard {
+                    if !self.evaluate(guard)?.is_truthy() {
+                        return Ok(None);
+                    }
+                }
+                self.evaluate(&arm.body).map(Some)
+            })?;
+            if let Some(result) = result {
+                return Ok(result);
+            }
+        }
+
+        Err(InterpreterError::NoMatchingArm(value))
+    }
+
+    fn visit_set_index_expr(
+        &self,
+        object: &Expr,
+        _bracket: &Token,
+        index: &Expr,
+        value: &Expr,
+    ) -> Result<Object, InterpreterError> {
+        let object = self.evaluate(object)?;
+        let index = self.evaluate(index)?;
+        let value = self.evaluate(value)?;
+        self.set_index(object, index, value)
+    }
+
+    fn visit_throw_expr(&self, keyword: &Token, value: &Expr) -> Result<Object, InterpreterError> {
+        Err(InterpreterError::Thrown(
+            keyword.loc.line,
+            self.evaluate(value)?,
+        ))
+    }
+
+    fn visit_try_expr(
+        &self,
+        body: &Expr,
+        catch: Option<&(Token, Box<Expr>)>,
+        finally: Option<&Expr>,
+    ) -> Result<Object, InterpreterError> {
+        let mut result = self.evaluate(body);
+
+        if let (Err(error), Some((name, handler))) = (&result, catch) {
+            let mut scope = Environment::new(self.environment.borrow().clone());
+            scope.define(&name.loc.lexeme, self.error_object(error));
+            result = self.with_environment(scope, || self.evaluate(handler));
+        }
+
+        if let Some(finally) = finally {
+            self.evaluate(finally)?;
+        }
+
+        result
+    }
+
     fn visit_unary_expr(&self, operator: &Token, right: &Expr) -> Result<Object, InterpreterError> {
         let right = self.evaluate(right)?;
 
         match operator.r#type {
             TokenType::Minus => match right {
                 Object::Number(num) => Ok(Object::Number(-num)),
+                Object::Int(int) => Ok(int
+                    .checked_neg()
+                    .map_or_else(|| Object::BigInt(-BigInt::from(int)), Object::Int)),
+                Object::BigInt(big) => Ok(Object::BigInt(-big)),
+                Object::Decimal(dec) => Ok(Object::Decimal(-dec)),
                 _ => Err(InterpreterError::InvalidNumberOperand(right)),
             },
+            TokenType::Tilde => match right {
+                Object::Int(int) => Ok(Object::Int(!int)),
+                Object::BigInt(big) => Ok(Object::BigInt(!big)),
+                _ => Err(InterpreterError::InvalidIntegerOperand(right)),
+            },
             TokenType::Bang => Ok(Object::Bool(right.is_truthy())),
             _ => Err(InterpreterError::InvalidNumberOperand(right)),
         }
     }
+
+    fn visit_update_expr(
+        &self,
+        target: &Expr,
+        operator: &Token,
+        prefix: bool,
+    ) -> Result<Object, InterpreterError> {
+        let operator = operator.compound_operator();
+        let (old, new) =
+            self.update_target(target, |old| self.binary(&operator, old, Object::Int(1)))?;
+        Ok(if prefix { new } else { old })
+    }
+
+    fn visit_variable_expr(&self, name: &Token) -> Result<Object, InterpreterError> {
+        self.environment.borrow().borrow().get(name)
+    }
 }
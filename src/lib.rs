@@ -1,40 +1,59 @@
-use expr::AstPrinter;
+use checker::TypeChecker;
+use expr::Expr;
 use interpreter::Interpreter;
 use parser::Parser;
 use scanner::Scanner;
 use thiserror::Error;
 use token::{Object, Token};
 
+pub mod checker;
+pub mod environment;
 pub mod errors;
 pub mod expr;
 pub mod interpreter;
+pub mod list;
 pub mod lox;
+pub mod map;
+pub mod native;
 pub mod parser;
 pub mod scanner;
 pub mod token;
 
-pub fn run(source: &str) -> Result<(), InterpreterError> {
+/// Scans and parses `source`, failing if either reported an error.
+fn parse(source: &str) -> Result<Expr, InterpreterError> {
     let mut scanner = Scanner::new(source);
     let tokens = scanner.scan_tokens();
     let mut parser = Parser::new(&tokens);
-    let expr = parser.parse();
-    let mut interpreter = Interpreter;
+    match parser.parse() {
+        Some(expr) if !scanner.had_error => Ok(expr),
+        _ => Err(InterpreterError::ParseError),
+    }
+}
 
-    match expr {
-        Some(e) => interpreter.interpret(&e)?,
-        None => {
-            panic!("Error while scanning tokens");
-        }
+pub fn run(source: &str) -> Result<(), InterpreterError> {
+    let expr = parse(source)?;
+    let mut interpreter = Interpreter::default();
+
+    interpreter.interpret(&expr)
+}
+
+/// Type checks `source` without running it.
+pub fn check(source: &str) -> Result<(), InterpreterError> {
+    let expr = parse(source)?;
+
+    if TypeChecker::default().check(&expr) {
+        Ok(())
+    } else {
+        Err(InterpreterError::TypeError)
     }
-    Ok(())
 }
 
-pub fn error(line: usize, msg: &str) {
-    report(line, "", msg);
+pub fn error(line: usize, column: usize, msg: &str) {
+    report(line, column, "", msg);
 }
 
-pub fn report(line: usize, loc: &str, msg: &str) {
-    eprintln!("[line: {line}] Error {loc}: {msg}");
+pub fn report(line: usize, column: usize, loc: &str, msg: &str) {
+    eprintln!("[line: {line}, column: {column}] Error {loc}: {msg}");
 }
 
 #[derive(Error, Debug)]
@@ -45,6 +64,77 @@ pub enum InterpreterError {
     InvalidNumberOperands(Object, Object),
     #[error("Operands {0} and {1} must be a number or string.")]
     InvalidStringOrNumberOperands(Object, Object),
-    #[error("Operator {1} cannot be applied to {1} and {2}.")]
-    InvalidOperatorError(Object, Token, Object),
+    #[error("Operator {1} cannot be applied to {0} and {2}.")]
+    InvalidOperatorError(Object, Box<Token>, Object),
+    #[error("Only lists and maps can be indexed, not {0}.")]
+    NotIndexable(Object),
+    #[error("List index {0} must be a non-negative int.")]
+    InvalidIndex(Object),
+    #[error("List index {0} is out of bounds for a list of length {1}.")]
+    IndexOutOfBounds(Object, usize),
+    #[error("Cannot pop from an empty list.")]
+    EmptyList,
+    #[error("Cannot modify a frozen {0}.")]
+    Frozen(&'static str),
+    #[error("Map key {0} is not hashable.")]
+    UnhashableKey(Object),
+    #[error("Key {0} not found in map.")]
+    MissingKey(Object),
+    #[error("Division by zero.")]
+    DivisionByZero,
+    #[error("Decimal overflow.")]
+    DecimalOverflow,
+    #[error("Decimal result has too many digits to be exact.")]
+    DecimalPrecisionLoss,
+    #[error("Cannot mix decimal and float operands {0} and {1}.")]
+    MixedDecimalAndFloat(Object, Object),
+    #[error("Operand {0} must be an int.")]
+    InvalidIntegerOperand(Object),
+    #[error("Operands {0} and {1} must be ints.")]
+    InvalidIntegerOperands(Object, Object),
+    #[error("Shift count {0} must not be negative.")]
+    NegativeShiftCount(Object),
+    #[error("Shift count {0} is too large.")]
+    ShiftTooLarge(Object),
+    #[error("Exponent {0} is too large.")]
+    ExponentTooLarge(Object),
+    #[error("Decimal exponent {0} must be a whole number.")]
+    FractionalDecimalExponent(Object),
+    #[error("Can only call functions, not {0}.")]
+    NotCallable(Object),
+    #[error("Expected {0} arguments but got {1}.")]
+    ArityMismatch(usize, usize),
+    #[error("Argument {1} to {0} must be {2}.")]
+    InvalidArgument(&'static str, Object, &'static str),
+    #[error("Cannot convert {0} to a {1}.")]
+    InvalidConversion(Object, &'static str),
+    #[error("Undefined variable '{0}'.")]
+    UndefinedVariable(String),
+    #[error("No match arm matches {0}.")]
+    NoMatchingArm(Object),
+    #[error("Uncaught exception {1} at line {0}.")]
+    Thrown(usize, Object),
+    /// A runtime error and the line it happened on.
+    #[error("{1}\n[line {0}]")]
+    Located(usize, Box<InterpreterError>),
+    #[error("Only lists, maps and strings can be iterated, not {0}.")]
+    NotIterable(Object),
+    #[error("Could not parse source.")]
+    ParseError,
+    #[error("Source has type errors.")]
+    TypeError,
+}
+
+impl InterpreterError {
+    /// Records that the error happened on `line`, unless it already knows
+    /// where it happened.
+    pub fn at(self, line: usize) -> Self {
+        match self {
+            InterpreterError::Located(..)
+            | InterpreterError::Thrown(..)
+            | InterpreterError::ParseError
+            | InterpreterError::TypeError => self,
+            _ => InterpreterError::Located(line, Box::new(self)),
+        }
+    }
 }
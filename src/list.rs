@@ -0,0 +1,46 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::token::Object;
+
+/// The elements backing `Object::List`.
+///
+/// A frozen list can't be changed by the program. The interpreter checks
+/// this before writing; the list itself doesn't enforce it.
+#[derive(Default, Debug, Clone)]
+pub struct List {
+    elements: Vec<Object>,
+    frozen: bool,
+}
+
+impl List {
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+}
+
+impl From<Vec<Object>> for List {
+    fn from(elements: Vec<Object>) -> Self {
+        Self {
+            elements,
+            frozen: false,
+        }
+    }
+}
+
+impl Deref for List {
+    type Target = Vec<Object>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.elements
+    }
+}
+
+impl DerefMut for List {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.elements
+    }
+}
@@ -1,6 +1,6 @@
 use std::{fs::read_to_string, io, process::exit};
 
-use crate::{run, InterpreterError};
+use crate::{check, run, InterpreterError};
 
 #[derive(Default)]
 pub struct Lox {
@@ -9,9 +9,25 @@ pub struct Lox {
 
 impl Lox {
     pub fn run_file(&mut self, path: &str) -> Result<(), InterpreterError> {
-        if let Err(e) = run(&read_to_string(path).expect("Invalid file location")) {
-            eprintln!("{}", e);
-            exit(70);
+        match run(&read_to_string(path).expect("Invalid file location")) {
+            Err(InterpreterError::ParseError) => exit(65),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(70);
+            }
+            Ok(()) => {}
+        }
+        Ok(())
+    }
+
+    pub fn check_file(&mut self, path: &str) -> Result<(), InterpreterError> {
+        match check(&read_to_string(path).expect("Invalid file location")) {
+            Err(InterpreterError::ParseError | InterpreterError::TypeError) => exit(65),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(70);
+            }
+            Ok(()) => {}
         }
         Ok(())
     }
@@ -23,12 +39,14 @@ impl Lox {
             let line = io::stdin().read_line(&mut buf);
             match line {
                 Ok(0) => break,
-                Ok(_) => {
-                    if let Err(e) = run(&buf) {
+                Ok(_) => match run(&buf) {
+                    Err(InterpreterError::ParseError) => self.had_error = true,
+                    Err(e) => {
                         self.had_error = true;
                         eprintln!("{}", e);
                     }
-                }
+                    Ok(()) => {}
+                },
                 Err(e) => {
                     self.had_error = true;
                     eprintln!("{}", e);
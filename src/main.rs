@@ -14,8 +14,11 @@ fn main() -> Result<(), InterpreterError> {
         2 => {
             lox.run_file(&arguments[1])?;
         }
+        3 if arguments[1] == "check" => {
+            lox.check_file(&arguments[2])?;
+        }
         _ => {
-            eprintln!("Usage: treelox2 [script]");
+            eprintln!("Usage: treelox2 [check] [script]");
             exit(64);
         }
     }
@@ -0,0 +1,100 @@
+use std::{collections::HashMap, fmt};
+
+use crate::token::Object;
+
+/// An insertion-ordered hash map backing `Object::Map`.
+///
+/// Keys must satisfy `Object::is_hashable`; callers check this before
+/// inserting or looking up so the `Hash`/`Eq` contract on `Object` holds.
+/// Likewise, callers check that a map isn't frozen before changing it.
+#[derive(Default, Debug, Clone)]
+pub struct Map {
+    entries: Vec<(Object, Object)>,
+    indices: HashMap<Object, usize>,
+    frozen: bool,
+}
+
+impl Map {
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn get(&self, key: &Object) -> Option<&Object> {
+        self.indices.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn insert(&mut self, key: Object, value: Object) {
+        match self.indices.get(&key) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.indices.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    /// Removes `key`, returning its value. Later entries keep their order.
+    pub fn remove(&mut self, key: &Object) -> Option<Object> {
+        let i = self.indices.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        for index in self.indices.values_mut() {
+            if *index > i {
+                *index -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Object, &Object)> {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+
+    /// See `Object::fmt_nested`.
+    pub(crate) fn fmt_nested(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        seen: &mut Vec<*const ()>,
+    ) -> fmt::Result {
+        f.write_str("{")?;
+        for (i, (key, value)) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}: ", key)?;
+            value.fmt_nested(f, seen)?;
+        }
+        f.write_str("}")
+    }
+
+    /// See `Object::eq_nested`.
+    pub(crate) fn eq_nested(&self, other: &Map, seen: &mut Vec<(*const (), *const ())>) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(k, v)| other.get(k).is_some_and(|other| v.eq_nested(other, seen)))
+    }
+}
+
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_nested(other, &mut vec![])
+    }
+}
+
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_nested(f, &mut vec![])
+    }
+}
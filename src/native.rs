@@ -0,0 +1,434 @@
+use std::{
+    cell::RefCell,
+    cmp::Ordering::{Equal, Greater, Less},
+    fmt,
+    rc::Rc,
+};
+
+use num_bigint::BigInt;
+use num_traits::{Signed, Zero};
+use rust_decimal::Decimal;
+
+use crate::{
+    interpreter::{exact_decimal, Interpreter},
+    list::List,
+    map::Map,
+    token::Object,
+    InterpreterError,
+};
+
+/// A function built into the interpreter, such as `len`.
+#[derive(Clone, Copy)]
+pub struct Native {
+    pub name: &'static str,
+    pub arity: usize,
+    pub function: fn(&Interpreter, &[Object]) -> Result<Object, InterpreterError>,
+}
+
+impl fmt::Debug for Native {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+/// The natives defined in the global scope of every program.
+pub const NATIVES: &[Native] = &[
+    Native {
+        name: "len",
+        arity: 1,
+        function: len,
+    },
+    Native {
+        name: "push",
+        arity: 2,
+        function: push,
+    },
+    Native {
+        name: "pop",
+        arity: 1,
+        function: pop,
+    },
+    Native {
+        name: "insert",
+        arity: 3,
+        function: insert,
+    },
+    Native {
+        name: "remove",
+        arity: 2,
+        function: remove,
+    },
+    Native {
+        name: "keys",
+        arity: 1,
+        function: keys,
+    },
+    Native {
+        name: "values",
+        arity: 1,
+        function: values,
+    },
+    Native {
+        name: "has",
+        arity: 2,
+        function: has,
+    },
+    Native {
+        name: "delete",
+        arity: 2,
+        function: delete,
+    },
+    Native {
+        name: "freeze",
+        arity: 1,
+        function: freeze,
+    },
+    Native {
+        name: "divide",
+        arity: 4,
+        function: divide,
+    },
+    Native {
+        name: "round",
+        arity: 3,
+        function: round,
+    },
+    Native {
+        name: "decimal",
+        arity: 1,
+        function: decimal,
+    },
+    Native {
+        name: "float",
+        arity: 1,
+        function: float,
+    },
+    Native {
+        name: "string",
+        arity: 1,
+        function: string,
+    },
+];
+
+/// The list passed to the native `name`.
+fn list(name: &'static str, value: &Object) -> Result<Rc<RefCell<List>>, InterpreterError> {
+    match value {
+        Object::List(list) => Ok(list.clone()),
+        _ => Err(InterpreterError::InvalidArgument(
+            name,
+            value.clone(),
+            "a list",
+        )),
+    }
+}
+
+/// The map passed to the native `name`.
+fn map(name: &'static str, value: &Object) -> Result<Rc<RefCell<Map>>, InterpreterError> {
+    match value {
+        Object::Map(map) => Ok(map.clone()),
+        _ => Err(InterpreterError::InvalidArgument(
+            name,
+            value.clone(),
+            "a map",
+        )),
+    }
+}
+
+/// The list passed to the native `name`, which changes it, so it must not
+/// be frozen.
+fn mutable_list(
+    interpreter: &Interpreter,
+    name: &'static str,
+    value: &Object,
+) -> Result<Rc<RefCell<List>>, InterpreterError> {
+    let list = list(name, value)?;
+    interpreter.check_mutable(value)?;
+    Ok(list)
+}
+
+/// The map passed to the native `name`, which changes it, so it must not
+/// be frozen.
+fn mutable_map(
+    interpreter: &Interpreter,
+    name: &'static str,
+    value: &Object,
+) -> Result<Rc<RefCell<Map>>, InterpreterError> {
+    let map = map(name, value)?;
+    interpreter.check_mutable(value)?;
+    Ok(map)
+}
+
+/// The number of elements in a list, entries in a map or characters in a
+/// string.
+fn len(_: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    let len = match &arguments[0] {
+        Object::List(list) => list.borrow().len(),
+        Object::Map(map) => map.borrow().len(),
+        Object::String(string) => string.chars().count(),
+        value => {
+            return Err(InterpreterError::InvalidArgument(
+                "len",
+                value.clone(),
+                "a list, map or string",
+            ))
+        }
+    };
+    Ok(Object::Int(len as i64))
+}
+
+/// Appends a value to the end of a list.
+fn push(interpreter: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    mutable_list(interpreter, "push", &arguments[0])?
+        .borrow_mut()
+        .push(arguments[1].clone());
+    Ok(Object::Nil)
+}
+
+/// Removes and returns the last element of a list.
+fn pop(interpreter: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    mutable_list(interpreter, "pop", &arguments[0])?
+        .borrow_mut()
+        .pop()
+        .ok_or(InterpreterError::EmptyList)
+}
+
+/// Inserts a value before the element at an index, or at the end of the
+/// list if the index is its length.
+fn insert(interpreter: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    let list = mutable_list(interpreter, "insert", &arguments[0])?;
+    let mut list = list.borrow_mut();
+    let i = if arguments[1].as_bigint() == Some(BigInt::from(list.len())) {
+        list.len()
+    } else {
+        interpreter.list_index(&arguments[1], list.len())?
+    };
+    list.insert(i, arguments[2].clone());
+    Ok(Object::Nil)
+}
+
+/// Removes and returns the element at an index, shifting the rest down.
+fn remove(interpreter: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    let list = mutable_list(interpreter, "remove", &arguments[0])?;
+    let mut list = list.borrow_mut();
+    let i = interpreter.list_index(&arguments[1], list.len())?;
+    Ok(list.remove(i))
+}
+
+/// A list of a map's keys, in insertion order.
+fn keys(_: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    let keys: Vec<Object> = map("keys", &arguments[0])?
+        .borrow()
+        .iter()
+        .map(|(key, _)| key.clone())
+        .collect();
+    Ok(Object::List(Rc::new(RefCell::new(List::from(keys)))))
+}
+
+/// A list of a map's values, in the order of their keys.
+fn values(_: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    let values: Vec<Object> = map("values", &arguments[0])?
+        .borrow()
+        .iter()
+        .map(|(_, value)| value.clone())
+        .collect();
+    Ok(Object::List(Rc::new(RefCell::new(List::from(values)))))
+}
+
+/// Whether a map has a key.
+fn has(interpreter: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    let map = map("has", &arguments[0])?;
+    let key = interpreter.map_key(arguments[1].clone())?;
+    let has = map.borrow().get(&key).is_some();
+    Ok(Object::Bool(has))
+}
+
+/// Removes a key from a map, returning its value.
+fn delete(interpreter: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    let map = mutable_map(interpreter, "delete", &arguments[0])?;
+    let key = interpreter.map_key(arguments[1].clone())?;
+    let value = map.borrow_mut().remove(&key);
+    value.ok_or(InterpreterError::MissingKey(key))
+}
+
+/// Makes a list or map read-only and returns it, so that writing to it is
+/// a runtime error. Freezing is shallow: lists and maps inside it can still
+/// change. Other values can't change anyway and are returned as they are.
+fn freeze(_: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    match &arguments[0] {
+        Object::List(list) => list.borrow_mut().freeze(),
+        Object::Map(map) => map.borrow_mut().freeze(),
+        _ => {}
+    }
+    Ok(arguments[0].clone())
+}
+
+/// How `divide` and `round` round a result that has too many decimal
+/// places.
+#[derive(Clone, Copy)]
+enum Rounding {
+    HalfEven,
+    HalfUp,
+    HalfDown,
+    Up,
+    Down,
+    Floor,
+    Ceiling,
+}
+
+impl Rounding {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "half_even" => Some(Rounding::HalfEven),
+            "half_up" => Some(Rounding::HalfUp),
+            "half_down" => Some(Rounding::HalfDown),
+            "up" => Some(Rounding::Up),
+            "down" => Some(Rounding::Down),
+            "floor" => Some(Rounding::Floor),
+            "ceiling" => Some(Rounding::Ceiling),
+            _ => None,
+        }
+    }
+
+    /// Divides `n` by `d`, rounding the quotient to a whole number. "Up"
+    /// and "down" round away from and toward zero, and the "half" modes
+    /// round to the nearest number, breaking ties as named.
+    fn divide(self, n: &BigInt, d: &BigInt) -> BigInt {
+        let (quotient, remainder) = (n / d, n % d);
+        if remainder.is_zero() {
+            return quotient;
+        }
+
+        let negative = n.is_negative() != d.is_negative();
+        let half = (remainder.abs() * 2u32).cmp(&d.abs());
+        let away = match self {
+            Rounding::HalfEven => half == Greater || (half == Equal && quotient.bit(0)),
+            Rounding::HalfUp => half != Less,
+            Rounding::HalfDown => half == Greater,
+            Rounding::Up => true,
+            Rounding::Down => false,
+            Rounding::Floor => negative,
+            Rounding::Ceiling => !negative,
+        };
+        match (away, negative) {
+            (false, _) => quotient,
+            (true, false) => quotient + 1,
+            (true, true) => quotient - 1,
+        }
+    }
+}
+
+/// The decimal, int or bigint passed to the native `name`, as a decimal.
+fn decimal_argument(name: &'static str, value: &Object) -> Result<Decimal, InterpreterError> {
+    match value {
+        Object::Decimal(_) | Object::Int(_) | Object::BigInt(_) => {
+            value.as_decimal().ok_or(InterpreterError::DecimalOverflow)
+        }
+        _ => Err(InterpreterError::InvalidArgument(
+            name,
+            value.clone(),
+            "a decimal or int",
+        )),
+    }
+}
+
+/// The number of decimal places passed to the native `name`.
+fn places_argument(name: &'static str, value: &Object) -> Result<u32, InterpreterError> {
+    match value {
+        Object::Int(places @ 0..=28) => Ok(*places as u32),
+        _ => Err(InterpreterError::InvalidArgument(
+            name,
+            value.clone(),
+            "an int from 0 to 28",
+        )),
+    }
+}
+
+/// The rounding mode passed to the native `name`.
+fn rounding_argument(name: &'static str, value: &Object) -> Result<Rounding, InterpreterError> {
+    match value {
+        Object::String(mode) => Rounding::parse(mode),
+        _ => None,
+    }
+    .ok_or(InterpreterError::InvalidArgument(
+        name,
+        value.clone(),
+        "one of half_even, half_up, half_down, up, down, floor or ceiling",
+    ))
+}
+
+/// `l / r` with exactly `places` decimal places, rounded once with `mode`.
+fn rounded_quotient(
+    l: Decimal,
+    r: Decimal,
+    places: u32,
+    mode: Rounding,
+) -> Result<Decimal, InterpreterError> {
+    if r.is_zero() {
+        return Err(InterpreterError::DivisionByZero);
+    }
+    // l / r * 10^places, with both mantissas scaled to whole numbers.
+    let ten = BigInt::from(10);
+    let n = BigInt::from(l.mantissa()) * ten.pow(r.scale() + places);
+    let d = BigInt::from(r.mantissa()) * ten.pow(l.scale());
+    exact_decimal(mode.divide(&n, &d), places)
+}
+
+/// `divide(l, r, places, mode)` divides two decimals, rounding the quotient
+/// to `places` decimal places with `mode`, such as "half_even".
+fn divide(_: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    let l = decimal_argument("divide", &arguments[0])?;
+    let r = decimal_argument("divide", &arguments[1])?;
+    let places = places_argument("divide", &arguments[2])?;
+    let mode = rounding_argument("divide", &arguments[3])?;
+    rounded_quotient(l, r, places, mode).map(Object::Decimal)
+}
+
+/// `round(value, places, mode)` rounds a decimal to `places` decimal
+/// places with `mode`.
+fn round(_: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    let value = decimal_argument("round", &arguments[0])?;
+    let places = places_argument("round", &arguments[1])?;
+    let mode = rounding_argument("round", &arguments[2])?;
+    rounded_quotient(value, Decimal::ONE, places, mode).map(Object::Decimal)
+}
+
+/// Converts a number or a string to a decimal. A float converts from its
+/// shortest printed form, so `decimal(0.1)` is `0.1d`. A value with too
+/// many digits to be exact is an error.
+fn decimal(_: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    let value = &arguments[0];
+    let error = || InterpreterError::InvalidConversion(value.clone(), "decimal");
+    match value {
+        Object::Decimal(_) | Object::Int(_) | Object::BigInt(_) => {
+            value.as_decimal().map(Object::Decimal).ok_or_else(error)
+        }
+        Object::Number(num) if num.is_finite() => Decimal::from_str_exact(&num.to_string())
+            .map(Object::Decimal)
+            .map_err(|_| error()),
+        Object::String(string) => if string.contains(['e', 'E']) {
+            Decimal::from_scientific(string)
+        } else {
+            Decimal::from_str_exact(string)
+        }
+        .map(Object::Decimal)
+        .map_err(|_| error()),
+        _ => Err(error()),
+    }
+}
+
+/// Converts a number or a string to a float, rounding if need be.
+fn float(_: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    let value = &arguments[0];
+    let float = match value {
+        Object::String(string) => string.parse().ok(),
+        _ => value.as_f64(),
+    };
+    float
+        .map(Object::Number)
+        .ok_or_else(|| InterpreterError::InvalidConversion(value.clone(), "float"))
+}
+
+/// Converts any value to the string it prints as.
+fn string(_: &Interpreter, arguments: &[Object]) -> Result<Object, InterpreterError> {
+    Ok(Object::String(arguments[0].to_string()))
+}
@@ -1,9 +1,12 @@
 use crate::{
     errors::Errors,
-    expr::Expr,
+    expr::{Expr, MatchArm, Pattern},
     token::{Object, Token, TokenType},
 };
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError;
+
 #[derive(Default, Debug, Clone)]
 pub struct Parser {
     tokens: Vec<Token>,
@@ -20,23 +23,89 @@ impl Parser {
     }
 
     pub fn parse(&mut self) -> Option<Expr> {
-        let res = self.expression();
-        if self.errors.had_error {
-            None
-        } else {
-            Some(res)
+        match self.expression() {
+            Ok(expr) if !self.errors.had_error => Some(expr),
+            _ => None,
         }
     }
 
-    fn expression(&mut self) -> Expr {
-        self.equality()
+    fn expression(&mut self) -> Result<Expr, ParseError> {
+        self.comma()
     }
 
-    fn equality(&mut self) -> Expr {
-        let mut left = self.comparison();
+    fn comma(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.assignment()?;
+
+        while self.r#match(&[TokenType::Comma]) {
+            let operator = self.previous().clone();
+            let right = Box::new(self.assignment()?);
+            left = Expr::Binary {
+                left: Box::new(left),
+                operator,
+                right,
+            };
+        }
+
+        Ok(left)
+    }
+
+    fn assignment(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.equality()?;
+
+        if self.r#match(&[TokenType::Equal]) {
+            let equals = self.previous().clone();
+            let value = Box::new(self.assignment()?);
+
+            if let Expr::Index {
+                object,
+                bracket,
+                index,
+            } = expr
+            {
+                return Ok(Expr::SetIndex {
+                    object,
+                    bracket,
+                    index,
+                    value,
+                });
+            }
+
+            self.error(&equals, "Invalid assignment target.");
+        }
+
+        if self.r#match(&[
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+            TokenType::PercentEqual,
+        ]) {
+            let operator = self.previous().clone();
+            let value = Box::new(self.assignment()?);
+            return Ok(Expr::CompoundAssign {
+                target: self.assignment_target(expr, &operator)?,
+                operator,
+                value,
+            });
+        }
+
+        Ok(expr)
+    }
+
+    /// Checks that `expr` can be assigned to by a compound assignment or an
+    /// increment or decrement.
+    fn assignment_target(&mut self, expr: Expr, operator: &Token) -> Result<Box<Expr>, ParseError> {
+        match expr {
+            Expr::Index { .. } => Ok(Box::new(expr)),
+            _ => Err(self.error(operator, "Invalid assignment target.")),
+        }
+    }
+
+    fn equality(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.comparison()?;
         while self.r#match(&[TokenType::BangEqual, TokenType::EqualEqual]) {
             let operator = self.previous().clone();
-            let right = Box::new(self.comparison());
+            let right = Box::new(self.comparison()?);
             left = Expr::Binary {
                 left: Box::new(left),
                 operator,
@@ -44,11 +113,11 @@ impl Parser {
             }
         }
 
-        left
+        Ok(left)
     }
 
-    fn comparison(&mut self) -> Expr {
-        let mut left = self.term();
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.bit_or()?;
 
         while self.r#match(&[
             TokenType::Greater,
@@ -57,7 +126,7 @@ impl Parser {
             TokenType::LessEqual,
         ]) {
             let operator = self.previous().clone();
-            let right = Box::new(self.term());
+            let right = Box::new(self.bit_or()?);
             left = Expr::Binary {
                 left: Box::new(left),
                 operator,
@@ -65,15 +134,79 @@ impl Parser {
             }
         }
 
-        left
+        Ok(left)
+    }
+
+    fn bit_or(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.bit_xor()?;
+
+        while self.r#match(&[TokenType::Pipe]) {
+            let operator = self.previous().clone();
+            let right = Box::new(self.bit_xor()?);
+            left = Expr::Binary {
+                left: Box::new(left),
+                operator,
+                right,
+            };
+        }
+
+        Ok(left)
     }
 
-    fn term(&mut self) -> Expr {
-        let mut left = self.factor();
+    fn bit_xor(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.bit_and()?;
+
+        while self.r#match(&[TokenType::Caret]) {
+            let operator = self.previous().clone();
+            let right = Box::new(self.bit_and()?);
+            left = Expr::Binary {
+                left: Box::new(left),
+                operator,
+                right,
+            };
+        }
+
+        Ok(left)
+    }
+
+    fn bit_and(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.shift()?;
+
+        while self.r#match(&[TokenType::Ampersand]) {
+            let operator = self.previous().clone();
+            let right = Box::new(self.shift()?);
+            left = Expr::Binary {
+                left: Box::new(left),
+                operator,
+                right,
+            };
+        }
+
+        Ok(left)
+    }
+
+    fn shift(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.term()?;
+
+        while self.r#match(&[TokenType::LessLess, TokenType::GreaterGreater]) {
+            let operator = self.previous().clone();
+            let right = Box::new(self.term()?);
+            left = Expr::Binary {
+                left: Box::new(left),
+                operator,
+                right,
+            };
+        }
+
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.factor()?;
 
         while self.r#match(&[TokenType::Minus, TokenType::Plus]) {
             let operator = self.previous().clone();
-            let right = Box::new(self.factor());
+            let right = Box::new(self.factor()?);
             left = Expr::Binary {
                 left: Box::new(left),
                 operator,
@@ -81,15 +214,15 @@ impl Parser {
             };
         }
 
-        left
+        Ok(left)
     }
 
-    fn factor(&mut self) -> Expr {
-        let mut left = self.unary();
+    fn factor(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.unary()?;
 
-        while self.r#match(&[TokenType::Slash, TokenType::Star]) {
+        while self.r#match(&[TokenType::Slash, TokenType::Star, TokenType::Percent]) {
             let operator = self.previous().clone();
-            let right = Box::new(self.unary());
+            let right = Box::new(self.unary()?);
             left = Expr::Binary {
                 left: Box::new(left),
                 operator,
@@ -97,60 +230,376 @@ impl Parser {
             };
         }
 
-        left
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.r#match(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().clone();
+            let target = self.call()?;
+            let update = Expr::Update {
+                target: self.assignment_target(target, &operator)?,
+                operator,
+                prefix: true,
+            };
+            return self.exponent(update);
+        }
+
+        if self.r#match(&[TokenType::Bang, TokenType::Minus, TokenType::Tilde]) {
+            let operator = self.previous().clone();
+            let right = Box::new(self.unary()?);
+            return Ok(Expr::Unary { operator, right });
+        }
+
+        self.power()
+    }
+
+    /// `**` binds tighter than a unary operator on its left, so `-2 ** 2` is
+    /// `-(2 ** 2)`, and is right-associative.
+    fn power(&mut self) -> Result<Expr, ParseError> {
+        let left = self.call()?;
+        self.exponent(left)
     }
 
-    fn unary(&mut self) -> Expr {
-        if self.r#match(&[TokenType::Bang, TokenType::Minus]) {
+    /// Parses the `** right` that may follow `left`. A prefix increment or
+    /// decrement is its own left operand, so `++xs[0] ** 2` squares the
+    /// incremented element.
+    fn exponent(&mut self, left: Expr) -> Result<Expr, ParseError> {
+        if self.r#match(&[TokenType::StarStar]) {
             let operator = self.previous().clone();
-            let right = Box::new(self.unary());
-            return Expr::Unary { operator, right };
+            let right = Box::new(self.unary()?);
+            return Ok(Expr::Binary {
+                left: Box::new(left),
+                operator,
+                right,
+            });
         }
 
-        self.primary()
+        Ok(left)
     }
 
-    fn primary(&mut self) -> Expr {
+    fn call(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.primary()?;
+
+        loop {
+            if self.r#match(&[TokenType::LeftParen]) {
+                expr = self.finish_call(expr)?;
+            } else if self.r#match(&[TokenType::LeftBracket]) {
+                let index = Box::new(self.expression()?);
+                let bracket = self.consume(&TokenType::RightBracket, "Expect ']' after index.")?;
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    bracket,
+                    index,
+                };
+            } else {
+                break;
+            }
+        }
+
+        if self.r#match(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().clone();
+            return Ok(Expr::Update {
+                target: self.assignment_target(expr, &operator)?,
+                operator,
+                prefix: false,
+            });
+        }
+
+        Ok(expr)
+    }
+
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr, ParseError> {
+        let mut arguments = vec![];
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                if arguments.len() >= 255 {
+                    self.error(&self.peek().clone(), "Can't have more than 255 arguments.");
+                }
+                arguments.push(self.assignment()?);
+                if !self.r#match(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        let paren = self.consume(&TokenType::RightParen, "Expect ')' after arguments.")?;
+        Ok(Expr::Call {
+            callee: Box::new(callee),
+            paren,
+            arguments,
+        })
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
         if self.r#match(&[TokenType::False]) {
-            return Expr::Literal {
+            return Ok(Expr::Literal {
                 value: Object::Bool(false),
-            };
+            });
         }
         if self.r#match(&[TokenType::True]) {
-            return Expr::Literal {
+            return Ok(Expr::Literal {
                 value: Object::Bool(true),
-            };
+            });
         }
         if self.r#match(&[TokenType::Nil]) {
-            return Expr::Literal { value: Object::Nil };
+            return Ok(Expr::Literal { value: Object::Nil });
         }
 
         if self.r#match(&[TokenType::Number, TokenType::String]) {
-            return Expr::Literal {
+            return Ok(Expr::Literal {
                 value: self.previous().clone().literal.unwrap(),
-            };
+            });
+        }
+
+        if self.r#match(&[TokenType::Identifier]) {
+            return Ok(Expr::Variable {
+                name: self.previous().clone(),
+            });
+        }
+
+        if self.r#match(&[TokenType::Match]) {
+            return self.match_expr();
+        }
+
+        if self.r#match(&[TokenType::Throw]) {
+            let keyword = self.previous().clone();
+            let value = Box::new(self.assignment()?);
+            return Ok(Expr::Throw { keyword, value });
+        }
+
+        if self.r#match(&[TokenType::For]) {
+            return self.for_expr();
+        }
+
+        if self.r#match(&[TokenType::Try]) {
+            return self.try_expr();
+        }
+
+        if self.r#match(&[TokenType::Interpolation]) {
+            let mut parts = vec![];
+            loop {
+                parts.push(Expr::Literal {
+                    value: self.previous().clone().literal.unwrap(),
+                });
+                parts.push(self.expression()?);
+                self.consume(&TokenType::RightBrace, "Expect '}' after interpolation.")?;
+                if !self.r#match(&[TokenType::Interpolation]) {
+                    break;
+                }
+            }
+            let end = self.consume(&TokenType::String, "Expect end of string.")?;
+            parts.push(Expr::Literal {
+                value: end.literal.unwrap(),
+            });
+            return Ok(Expr::Interpolation { parts });
         }
 
         if self.r#match(&[TokenType::LeftParen]) {
-            let expr = Box::new(self.expression());
-            self.consume(&TokenType::RightParen, "Expect ')' after expression.");
-            return Expr::Grouping { expr };
+            let expr = Box::new(self.expression()?);
+            self.consume(&TokenType::RightParen, "Expect ')' after expression.")?;
+            return Ok(Expr::Grouping { expr });
+        }
+
+        if self.r#match(&[TokenType::LeftBracket]) {
+            let mut elements = vec![];
+            if !self.check(&TokenType::RightBracket) {
+                loop {
+                    elements.push(self.assignment()?);
+                    if !self.r#match(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(&TokenType::RightBracket, "Expect ']' after list elements.")?;
+            return Ok(Expr::List { elements });
+        }
+
+        // Blocks only start statements, so a brace in expression position is
+        // always a map literal.
+        if self.r#match(&[TokenType::LeftBrace]) {
+            let brace = self.previous().clone();
+            let mut entries = vec![];
+            if !self.check(&TokenType::RightBrace) {
+                loop {
+                    let key = self.assignment()?;
+                    self.consume(&TokenType::Colon, "Expect ':' after map key.")?;
+                    let value = self.assignment()?;
+                    entries.push((key, value));
+                    if !self.r#match(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(&TokenType::RightBrace, "Expect '}' after map entries.")?;
+            return Ok(Expr::Map { brace, entries });
         }
 
-        self.errors
-            .error_token(&self.peek().clone(), "Expect expression.");
-        panic!("Couldn't parse {:?}", self.peek());
+        Err(self.error(&self.peek().clone(), "Expect expression."))
     }
 
-    fn consume(&mut self, token_type: &TokenType, msg: &str) -> Option<Token> {
+    fn match_expr(&mut self) -> Result<Expr, ParseError> {
+        let keyword = self.previous().clone();
+        let subject = Box::new(self.assignment()?);
+        self.consume(&TokenType::LeftBrace, "Expect '{' after match value.")?;
+
+        let mut arms = vec![];
+        while !self.check(&TokenType::RightBrace) {
+            let pattern = self.pattern()?;
+            let guard = if self.r#match(&[TokenType::If]) {
+                Some(self.assignment()?)
+            } else {
+                None
+            };
+            self.consume(&TokenType::EqualGreater, "Expect '=>' after match pattern.")?;
+            let body = self.assignment()?;
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+            });
+            if !self.r#match(&[TokenType::Comma]) {
+                break;
+            }
+        }
+        self.consume(&TokenType::RightBrace, "Expect '}' after match arms.")?;
+
+        if !arms
+            .iter()
+            .any(|arm| arm.guard.is_none() && arm.pattern.is_irrefutable())
+        {
+            self.errors
+                .warning_token(&keyword, "Match has no wildcard arm.");
+        }
+
+        Ok(Expr::Match {
+            keyword,
+            subject,
+            arms,
+        })
+    }
+
+    fn for_expr(&mut self) -> Result<Expr, ParseError> {
+        let keyword = self.previous().clone();
+        self.consume(&TokenType::LeftParen, "Expect '(' after 'for'.")?;
+        let name = self.consume(&TokenType::Identifier, "Expect loop variable name.")?;
+        self.consume(&TokenType::In, "Expect 'in' after loop variable.")?;
+        let iterable = Box::new(self.expression()?);
+        self.consume(&TokenType::RightParen, "Expect ')' after for clause.")?;
+        let body = Box::new(self.assignment()?);
+        Ok(Expr::For {
+            keyword,
+            name,
+            iterable,
+            body,
+        })
+    }
+
+    fn try_expr(&mut self) -> Result<Expr, ParseError> {
+        let keyword = self.previous().clone();
+        let body = Box::new(self.block("try")?);
+
+        let catch = if self.r#match(&[TokenType::Catch]) {
+            self.consume(&TokenType::LeftParen, "Expect '(' after 'catch'.")?;
+            let name = self.consume(&TokenType::Identifier, "Expect error name.")?;
+            self.consume(&TokenType::RightParen, "Expect ')' after error name.")?;
+            Some((name, Box::new(self.block("catch")?)))
+        } else {
+            None
+        };
+
+        let finally = if self.r#match(&[TokenType::Finally]) {
+            Some(Box::new(self.block("finally")?))
+        } else {
+            None
+        };
+
+        if catch.is_none() && finally.is_none() {
+            return Err(self.error(&keyword, "Expect 'catch' or 'finally' after try block."));
+        }
+
+        Ok(Expr::Try {
+            body,
+            catch,
+            finally,
+        })
+    }
+
+    /// Parses a `{ expression }` body after the keyword `after`.
+    fn block(&mut self, after: &str) -> Result<Expr, ParseError> {
+        self.consume(
+            &TokenType::LeftBrace,
+            &format!("Expect '{{' after '{}'.", after),
+        )?;
+        let expr = self.expression()?;
+        self.consume(&TokenType::RightBrace, "Expect '}' after block.")?;
+        Ok(expr)
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, ParseError> {
+        let mut alternatives = vec![self.simple_pattern()?];
+        while self.r#match(&[TokenType::Pipe]) {
+            alternatives.push(self.simple_pattern()?);
+        }
+
+        if alternatives.len() == 1 {
+            return Ok(alternatives.remove(0));
+        }
+        if let Some(Pattern::Binding(name)) = alternatives
+            .iter()
+            .find(|pattern| matches!(pattern, Pattern::Binding(_)))
+        {
+            return Err(self.error(
+                &name.clone(),
+                "Cannot bind a name in an alternative pattern.",
+            ));
+        }
+        Ok(Pattern::Alternatives(alternatives))
+    }
+
+    fn simple_pattern(&mut self) -> Result<Pattern, ParseError> {
+        if self.r#match(&[TokenType::Identifier]) {
+            let name = self.previous().clone();
+            if name.loc.lexeme == "_" {
+                return Ok(Pattern::Wildcard);
+            }
+            return Ok(Pattern::Binding(name));
+        }
+
+        if self.r#match(&[TokenType::Minus]) {
+            let operator = self.previous().clone();
+            let number = self.consume(&TokenType::Number, "Expect number after '-' in pattern.")?;
+            return Ok(Pattern::Literal(Expr::Unary {
+                operator,
+                right: Box::new(Expr::Literal {
+                    value: number.literal.unwrap(),
+                }),
+            }));
+        }
+
+        match self.peek().r#type {
+            TokenType::False
+            | TokenType::True
+            | TokenType::Nil
+            | TokenType::Number
+            | TokenType::String => Ok(Pattern::Literal(self.primary()?)),
+            _ => Err(self.error(&self.peek().clone(), "Expect pattern.")),
+        }
+    }
+
+    fn consume(&mut self, token_type: &TokenType, msg: &str) -> Result<Token, ParseError> {
         if self.check(token_type) {
-            return Some(self.advance().clone());
+            return Ok(self.advance().clone());
         }
 
-        self.errors.error_token(&self.peek().clone(), msg);
-        None
+        Err(self.error(&self.peek().clone(), msg))
+    }
+
+    fn error(&mut self, token: &Token, msg: &str) -> ParseError {
+        self.errors.error_token(token, msg);
+        ParseError
     }
 
+    #[allow(dead_code)]
     fn synchronize(&mut self) {
         self.advance();
 
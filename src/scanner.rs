@@ -1,40 +1,69 @@
 use crate::error;
 use crate::token::{Location, Object, Token, TokenType};
+use num_bigint::BigInt;
+use rust_decimal::Decimal;
 use std::collections::HashMap;
 use std::process::exit;
+use std::str::FromStr;
+use unicode_xid::UnicodeXID;
+
+/// A `${` inside a string literal whose closing `}` hasn't been scanned yet.
+struct OpenInterpolation {
+    braces: usize,
+    line: usize,
+    column: usize,
+}
 
 pub struct Scanner {
-    source: Vec<u8>,
+    /// Whether a recoverable error, such as an unexpected character, was
+    /// reported. Scanning carries on so that later errors are reported too.
+    pub had_error: bool,
+    source: Vec<char>,
     tokens: Vec<Token>,
     start: usize,
     current: usize,
     line: usize,
+    line_start: usize,
+    start_line: usize,
+    start_column: usize,
+    interpolations: Vec<OpenInterpolation>,
     keywords: HashMap<String, TokenType>,
 }
 
 impl Scanner {
     pub fn new(source: &str) -> Self {
         Self {
-            source: source.as_bytes().to_vec(),
+            had_error: false,
+            source: source.chars().collect(),
             tokens: vec![],
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            start_line: 1,
+            start_column: 1,
+            interpolations: vec![],
             keywords: HashMap::from([
                 ("and".to_string(), TokenType::And),
+                ("catch".to_string(), TokenType::Catch),
                 ("class".to_string(), TokenType::Class),
                 ("else".to_string(), TokenType::Else),
                 ("false".to_string(), TokenType::False),
+                ("finally".to_string(), TokenType::Finally),
                 ("for".to_string(), TokenType::For),
                 ("fun".to_string(), TokenType::Fun),
                 ("if".to_string(), TokenType::If),
+                ("in".to_string(), TokenType::In),
+                ("match".to_string(), TokenType::Match),
                 ("nil".to_string(), TokenType::Nil),
                 ("or".to_string(), TokenType::Or),
                 ("print".to_string(), TokenType::Print),
                 ("return".to_string(), TokenType::Return),
                 ("super".to_string(), TokenType::Super),
                 ("this".to_string(), TokenType::This),
+                ("throw".to_string(), TokenType::Throw),
                 ("true".to_string(), TokenType::True),
+                ("try".to_string(), TokenType::Try),
                 ("var".to_string(), TokenType::Var),
                 ("while".to_string(), TokenType::While),
             ]),
@@ -44,14 +73,22 @@ impl Scanner {
     pub fn scan_tokens(&mut self) -> Vec<Token> {
         while !self.is_at_end() {
             self.start = self.current;
+            self.start_line = self.line;
+            self.start_column = self.column();
             self.scan_token();
         }
 
+        if let Some(open) = self.interpolations.last() {
+            error(open.line, open.column, "unterminated string interpolation.");
+            exit(65);
+        }
+
         self.tokens.push(Token {
             r#type: TokenType::Eof,
             loc: Location {
                 lexeme: "EOF".to_string(),
                 line: self.line,
+                column: self.column(),
             },
             literal: None,
         });
@@ -63,82 +100,168 @@ impl Scanner {
         self.current >= self.source.len()
     }
 
+    fn column(&self) -> usize {
+        self.current - self.line_start + 1
+    }
+
+    fn newline(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
+
     fn scan_token(&mut self) {
         let c = self.advance();
         use TokenType::*;
 
         match c {
-            b'(' => self.add_token(LeftParen, None),
-            b')' => self.add_token(RightParen, None),
-            b'{' => self.add_token(LeftBrace, None),
-            b'}' => self.add_token(RightBrace, None),
-            b',' => self.add_token(Comma, None),
-            b'.' => self.add_token(Dot, None),
-            b'-' => self.add_token(Minus, None),
-            b'+' => self.add_token(Plus, None),
-            b';' => self.add_token(Semicolon, None),
-            b'*' => self.add_token(Star, None),
-            b'!' => {
-                if self.r#match(b'=') {
+            '(' => self.add_token(LeftParen, None),
+            ')' => self.add_token(RightParen, None),
+            '{' => {
+                if let Some(open) = self.interpolations.last_mut() {
+                    open.braces += 1;
+                }
+                self.add_token(LeftBrace, None)
+            }
+            '}' => match self.interpolations.last_mut() {
+                Some(open) if open.braces == 1 => {
+                    self.interpolations.pop();
+                    self.add_token(RightBrace, None);
+                    self.start = self.current;
+                    self.start_column = self.column();
+                    self.string();
+                }
+                Some(open) => {
+                    open.braces -= 1;
+                    self.add_token(RightBrace, None)
+                }
+                None => self.add_token(RightBrace, None),
+            },
+            '[' => self.add_token(LeftBracket, None),
+            ']' => self.add_token(RightBracket, None),
+            ',' => self.add_token(Comma, None),
+            ':' => self.add_token(Colon, None),
+            '.' => self.add_token(Dot, None),
+            '-' => {
+                if self.r#match('-') {
+                    self.add_token(MinusMinus, None)
+                } else if self.r#match('=') {
+                    self.add_token(MinusEqual, None)
+                } else {
+                    self.add_token(Minus, None)
+                }
+            }
+            '+' => {
+                if self.r#match('+') {
+                    self.add_token(PlusPlus, None)
+                } else if self.r#match('=') {
+                    self.add_token(PlusEqual, None)
+                } else {
+                    self.add_token(Plus, None)
+                }
+            }
+            ';' => self.add_token(Semicolon, None),
+            '*' => {
+                if self.r#match('*') {
+                    self.add_token(StarStar, None)
+                } else if self.r#match('=') {
+                    self.add_token(StarEqual, None)
+                } else {
+                    self.add_token(Star, None)
+                }
+            }
+            '&' => self.add_token(Ampersand, None),
+            '|' => self.add_token(Pipe, None),
+            '^' => self.add_token(Caret, None),
+            '~' => self.add_token(Tilde, None),
+            '%' => {
+                if self.r#match('=') {
+                    self.add_token(PercentEqual, None)
+                } else {
+                    self.add_token(Percent, None)
+                }
+            }
+            '!' => {
+                if self.r#match('=') {
                     self.add_token(BangEqual, None)
                 } else {
                     self.add_token(Bang, None)
                 }
             }
-            b'=' => {
-                if self.r#match(b'=') {
+            '=' => {
+                if self.r#match('=') {
                     self.add_token(EqualEqual, None)
+                } else if self.r#match('>') {
+                    self.add_token(EqualGreater, None)
                 } else {
                     self.add_token(Equal, None)
                 }
             }
-            b'<' => {
-                if self.r#match(b'=') {
+            '<' => {
+                if self.r#match('=') {
                     self.add_token(LessEqual, None)
+                } else if self.r#match('<') {
+                    self.add_token(LessLess, None)
                 } else {
                     self.add_token(Less, None)
                 }
             }
-            b'>' => {
-                if self.r#match(b'=') {
+            '>' => {
+                if self.r#match('=') {
                     self.add_token(GreaterEqual, None)
+                } else if self.r#match('>') {
+                    self.add_token(GreaterGreater, None)
                 } else {
                     self.add_token(Greater, None)
                 }
             }
-            b'/' => {
-                if self.r#match(b'/') {
-                    while self.peek() != b'\n' && !self.is_at_end() {
+            '/' => {
+                if self.r#match('/') {
+                    while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                } else if self.r#match('*') {
+                    self.block_comment();
+                } else if self.r#match('=') {
+                    self.add_token(SlashEqual, None);
                 } else {
                     self.add_token(Slash, None);
                 }
             }
-            b' ' | b'\r' | b'\t' => {}
-            b'\n' => self.line += 1,
-            b'"' => self.string(),
-            b'0'..=b'9' => self.number(),
-            b'a'..=b'z' | b'A'..=b'Z' | b'_' => self.identifier(),
-            _ => error(self.line, &format!("Unexpected character: {}", c)),
+            ' ' | '\r' | '\t' => {}
+            '\n' => self.newline(),
+            '"' => self.string(),
+            'r' if self.peek() == '"' => self.raw_string(),
+            '0'..='9' => {
+                self.number();
+                self.number_end();
+            }
+            c if c == '_' || c.is_xid_start() => self.identifier(),
+            _ => {
+                error(
+                    self.line,
+                    self.start_column,
+                    &format!("Unexpected character: {}", c),
+                );
+                self.had_error = true;
+            }
         }
     }
 
-    fn peek(&self) -> u8 {
+    fn peek(&self) -> char {
         if self.is_at_end() {
-            return b'\0';
+            return '\0';
         }
         self.source[self.current]
     }
 
-    fn peek_next(&self) -> u8 {
+    fn peek_next(&self) -> char {
         if self.current + 1 >= self.source.len() {
-            return b'\0';
+            return '\0';
         }
         self.source[self.current + 1]
     }
 
-    fn r#match(&mut self, expected: u8) -> bool {
+    fn r#match(&mut self, expected: char) -> bool {
         if self.is_at_end() {
             return false;
         }
@@ -150,54 +273,272 @@ impl Scanner {
         true
     }
 
+    /// Skips a `/* ... */` comment. Comments nest, so every `/*` inside needs
+    /// its own `*/`.
+    fn block_comment(&mut self) {
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                error(
+                    self.start_line,
+                    self.start_column,
+                    "unterminated block comment.",
+                );
+                exit(65);
+            }
+
+            if self.peek() == '/' && self.peek_next() == '*' {
+                self.advance();
+                self.advance();
+                depth += 1;
+            } else if self.peek() == '*' && self.peek_next() == '/' {
+                self.advance();
+                self.advance();
+                depth -= 1;
+            } else if self.advance() == '\n' {
+                self.newline();
+            }
+        }
+    }
+
     fn number(&mut self) {
-        while self.peek().is_ascii_digit() {
+        let radix = match (self.previous(), self.peek()) {
+            ('0', 'x' | 'X') => 16,
+            ('0', 'o' | 'O') => 8,
+            ('0', 'b' | 'B') => 2,
+            _ => 10,
+        };
+
+        if radix != 10 {
             self.advance();
+            let mut digits = String::new();
+            self.digits(radix, &mut digits);
+            if digits.is_empty() {
+                let prefix: String = self.source[self.start..self.current].iter().collect();
+                self.number_error(&format!("Expect digits after '{}'.", prefix));
+            }
+            self.integer(&digits, radix);
+            return;
+        }
+
+        let mut text = String::from(self.previous());
+        self.digits(10, &mut text);
+        let mut is_float = false;
+
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+            is_float = true;
+            text.push(self.advance());
+            self.digits(10, &mut text);
+        }
+
+        if matches!(self.peek(), 'e' | 'E') {
+            is_float = true;
+            text.push(self.advance());
+            if matches!(self.peek(), '+' | '-') {
+                text.push(self.advance());
+            }
+            let digits = text.len();
+            self.digits(10, &mut text);
+            if text.len() == digits {
+                self.number_error("Expect digits in exponent.");
+            }
+        }
+
+        if self.r#match('d') {
+            let value = if text.contains(['e', 'E']) {
+                Decimal::from_scientific(&text)
+            } else {
+                Decimal::from_str(&text)
+            };
+            match value {
+                Ok(value) => self.add_token(TokenType::Number, Some(Object::Decimal(value))),
+                Err(_) => self.number_error("Decimal literal is out of range."),
+            }
+            return;
         }
 
-        if self.peek() == b'.' && self.peek_next().is_ascii_digit() {
+        if !is_float {
+            self.integer(&text, 10);
+            return;
+        }
+
+        match text.parse() {
+            Ok(value) => self.add_token(TokenType::Number, Some(Object::Number(value))),
+            Err(_) => self.number_error("Invalid number literal."),
+        }
+    }
+
+    /// Adds an integer literal token. Literals with an `n` suffix, or too
+    /// large for an `i64`, become bigints.
+    fn integer(&mut self, digits: &str, radix: u32) {
+        let big = self.r#match('n');
+        let value = match i64::from_str_radix(digits, radix) {
+            Ok(value) if !big => Object::Int(value),
+            _ => Object::BigInt(BigInt::parse_bytes(digits.as_bytes(), radix).unwrap()),
+        };
+        self.add_token(TokenType::Number, Some(value))
+    }
+
+    /// Appends a run of digits in `radix` to `text`, skipping `_` separators.
+    /// A separator must sit between two digits.
+    fn digits(&mut self, radix: u32, text: &mut String) {
+        let mut after_digit = !text.is_empty() && text.ends_with(|c: char| c.is_digit(radix));
+        loop {
+            let c = self.peek();
+            if c.is_digit(radix) {
+                text.push(c);
+                after_digit = true;
+            } else if c == '_' {
+                if !after_digit || !self.peek_next().is_digit(radix) {
+                    self.number_error("Digit separator '_' must be between digits.");
+                }
+                after_digit = false;
+            } else {
+                return;
+            }
             self.advance();
+        }
+    }
 
-            while self.peek().is_ascii_digit() {
+    /// Rejects a literal that runs straight into an identifier character,
+    /// such as a digit outside its radix in `0b102` or an unknown suffix in
+    /// `1.5n`.
+    fn number_end(&self) {
+        let c = self.peek();
+        if c.is_xid_continue() {
+            self.number_error(&format!("Unexpected character '{}' in number literal.", c));
+        }
+    }
+
+    fn number_error(&self, message: &str) -> ! {
+        error(self.start_line, self.start_column, message);
+        exit(65);
+    }
+
+    /// Scans the rest of a string literal, starting either just after the
+    /// opening quote or just after an interpolation's closing `}`.
+    ///
+    /// On reaching `${` this emits an `Interpolation` token holding the text
+    /// so far and goes back to scanning ordinary tokens until the matching
+    /// `}`, which resumes the string.
+    fn string(&mut self) {
+        let mut val = String::new();
+        while self.peek() != '"' && !self.is_at_end() {
+            if self.peek() == '$' && self.peek_next() == '{' {
+                self.interpolations.push(OpenInterpolation {
+                    braces: 1,
+                    line: self.line,
+                    column: self.column(),
+                });
+                self.advance();
                 self.advance();
+                self.add_token(TokenType::Interpolation, Some(Object::String(val)));
+                return;
+            }
+            if self.peek() == '\\' {
+                self.escape(&mut val);
+                continue;
             }
+            val.push(self.advance());
+            if self.previous() == '\n' {
+                self.newline();
+            }
+        }
+
+        if self.is_at_end() {
+            error(self.start_line, self.start_column, "unterminated string.");
+            exit(65);
         }
 
-        self.add_token(
-            TokenType::Number,
-            Some(Object::Number(
-                String::from_utf8_lossy(&self.source[self.start..self.current])
-                    .parse()
-                    .unwrap(),
-            )),
-        )
+        self.advance();
+
+        self.add_token(TokenType::String, Some(Object::String(val)))
     }
 
-    fn string(&mut self) {
-        while self.peek() != b'"' && !self.is_at_end() {
-            if self.peek() == b'\n' {
-                self.line += 1;
+    /// Scans a backslash escape inside a string and appends the character it
+    /// stands for to `val`.
+    fn escape(&mut self, val: &mut String) {
+        let column = self.column();
+        self.advance();
+        if self.is_at_end() {
+            return;
+        }
+
+        let c = match self.advance() {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '$' => '$',
+            'u' => self.unicode_escape(column),
+            _ => {
+                error(self.line, column, "Unknown escape sequence.");
+                exit(65);
             }
+        };
+        val.push(c);
+    }
+
+    fn unicode_escape(&mut self, column: usize) -> char {
+        if !self.r#match('{') {
+            error(self.line, column, "Expect '{' after '\\u'.");
+            exit(65);
+        }
+
+        let digits_start = self.current;
+        while self.peek().is_ascii_hexdigit() {
             self.advance();
         }
+        let digits: String = self.source[digits_start..self.current].iter().collect();
+
+        if !self.r#match('}') {
+            error(self.line, column, "Expect '}' after unicode escape.");
+            exit(65);
+        }
+
+        match u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+        {
+            Some(c) => c,
+            None => {
+                error(self.line, column, "Invalid unicode escape.");
+                exit(65);
+            }
+        }
+    }
+
+    /// Scans a raw string such as `r"\d+"`, whose backslashes and `${` are
+    /// kept literally.
+    fn raw_string(&mut self) {
+        self.advance();
+        while self.peek() != '"' && !self.is_at_end() {
+            if self.advance() == '\n' {
+                self.newline();
+            }
+        }
 
         if self.is_at_end() {
-            error(self.line, "unterminated string.");
+            error(self.start_line, self.start_column, "unterminated string.");
             exit(65);
         }
 
         self.advance();
 
-        let val = String::from_utf8_lossy(&self.source[(self.start + 1)..(self.current - 1)]);
-        self.add_token(TokenType::String, Some(Object::String(val.to_string())))
+        let val = self.source[(self.start + 2)..(self.current - 1)]
+            .iter()
+            .collect();
+        self.add_token(TokenType::String, Some(Object::String(val)))
     }
 
     fn identifier(&mut self) {
-        while self.peek().is_ascii_alphanumeric() {
+        while self.peek().is_xid_continue() {
             self.advance();
         }
 
-        let val = String::from_utf8_lossy(&self.source[self.start..self.current]).to_string();
+        let val: String = self.source[self.start..self.current].iter().collect();
         let token_type = self.keywords.get(&val);
         match token_type {
             Some(matching_type) => self.add_token(matching_type.clone(), None),
@@ -205,19 +546,24 @@ impl Scanner {
         }
     }
 
-    fn advance(&mut self) -> u8 {
+    fn previous(&self) -> char {
+        self.source[self.current - 1]
+    }
+
+    fn advance(&mut self) -> char {
         let res = self.source[self.current];
         self.current += 1;
         res
     }
 
     fn add_token(&mut self, token_type: TokenType, literal: Option<Object>) {
-        let text = self.source[self.start..self.current].to_vec();
+        let lexeme = self.source[self.start..self.current].iter().collect();
         self.tokens.push(Token {
             r#type: token_type,
             loc: Location {
-                lexeme: String::from_utf8_lossy(&text).to_string(),
-                line: self.line,
+                lexeme,
+                line: self.start_line,
+                column: self.start_column,
             },
             literal,
         });
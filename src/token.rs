@@ -1,77 +1,141 @@
-use std::fmt;
+use std::{
+    cell::RefCell,
+    fmt,
+    hash::{Hash, Hasher},
+    rc::Rc,
+    str::FromStr,
+};
+
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+
+use crate::{list::List, map::Map, native::Native};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Location {
     pub lexeme: String,
     pub line: usize,
+    pub column: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
+    Colon,
     Dot,
     Minus,
+    MinusMinus,
+    MinusEqual,
     Plus,
+    PlusPlus,
+    PlusEqual,
     Semicolon,
     Slash,
+    SlashEqual,
     Star,
+    StarStar,
+    StarEqual,
+    Percent,
+    PercentEqual,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
     Bang,
     BangEqual,
     Equal,
     EqualEqual,
+    EqualGreater,
     Greater,
     GreaterEqual,
     Less,
     LessEqual,
+    LessLess,
+    GreaterGreater,
     Identifier,
     String,
+    Interpolation,
     Number,
     And,
+    Catch,
     Class,
     Else,
     False,
+    Finally,
     Fun,
     For,
     If,
+    In,
+    Match,
     Nil,
     Or,
     Print,
     Return,
     Super,
     This,
+    Throw,
     True,
+    Try,
     Var,
     While,
     Eof,
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub r#type: TokenType,
     pub literal: Option<Object>,
     pub loc: Location,
 }
 
+impl Token {
+    /// The binary operator applied by a compound assignment, increment or
+    /// decrement, such as `+` for `+=` or `++`.
+    pub fn compound_operator(&self) -> Token {
+        let r#type = match self.r#type {
+            TokenType::PlusEqual | TokenType::PlusPlus => TokenType::Plus,
+            TokenType::MinusEqual | TokenType::MinusMinus => TokenType::Minus,
+            TokenType::StarEqual => TokenType::Star,
+            TokenType::SlashEqual => TokenType::Slash,
+            TokenType::PercentEqual => TokenType::Percent,
+            ref other => other.clone(),
+        };
+        Token {
+            r#type,
+            ..self.clone()
+        }
+    }
+}
+
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_fmt(format_args!(
-            "{:?} {} on line {}",
-            self.r#type, self.loc.lexeme, self.loc.line
+            "{:?} {} on line {}, column {}",
+            self.r#type, self.loc.lexeme, self.loc.line, self.loc.column
         ))
     }
 }
 
 #[non_exhaustive]
-#[derive(Default, Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Default, Debug, Clone)]
 pub enum Object {
     String(String),
     Number(f64),
+    Int(i64),
+    BigInt(BigInt),
+    Decimal(Decimal),
     Identifier(String),
     Bool(bool),
+    List(Rc<RefCell<List>>),
+    Map(Rc<RefCell<Map>>),
+    Native(Native),
     #[default]
     Nil,
 }
@@ -84,15 +148,224 @@ impl Object {
             _ => true,
         }
     }
+
+    /// The value of a numeric object as a float, for mixed arithmetic.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Object::Number(num) => Some(*num),
+            Object::Int(int) => Some(*int as f64),
+            Object::BigInt(big) => big.to_f64(),
+            Object::Decimal(dec) => dec.to_f64(),
+            _ => None,
+        }
+    }
+
+    /// The value of an int or bigint as a bigint.
+    pub fn as_bigint(&self) -> Option<BigInt> {
+        match self {
+            Object::Int(int) => Some(BigInt::from(*int)),
+            Object::BigInt(big) => Some(big.clone()),
+            _ => None,
+        }
+    }
+
+    /// The value of an int, bigint or decimal as a decimal.
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            Object::Decimal(dec) => Some(*dec),
+            Object::Int(int) => Some(Decimal::from(*int)),
+            Object::BigInt(big) => Decimal::from_str(&big.to_string()).ok(),
+            _ => None,
+        }
+    }
+
+    /// The value of a number with no fractional part, whatever its type.
+    fn whole_number(&self) -> Option<BigInt> {
+        match self {
+            Object::Int(int) => Some(BigInt::from(*int)),
+            Object::BigInt(big) => Some(big.clone()),
+            Object::Number(num) if num.fract() == 0.0 => BigInt::from_f64(*num),
+            Object::Decimal(dec) if dec.fract().is_zero() => {
+                BigInt::from_str(&dec.trunc().normalize().to_string()).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether this value may be used as a map key.
+    ///
+    /// Only immutable scalars are hashable. NaN is rejected because it is
+    /// not equal to itself and could never be looked up again.
+    pub fn is_hashable(&self) -> bool {
+        match self {
+            Object::Number(num) => !num.is_nan(),
+            Object::Int(_)
+            | Object::BigInt(_)
+            | Object::Decimal(_)
+            | Object::String(_)
+            | Object::Identifier(_)
+            | Object::Bool(_)
+            | Object::Nil => true,
+            Object::List(_) | Object::Map(_) | Object::Native(_) => false,
+        }
+    }
+}
+
+/// Numbers compare by value across types, so `3 == 3.0 == 3n == 3d`.
+/// Floats and decimals with a fractional part are never equal to each
+/// other, since few decimals have an exact float representation.
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Object::String(l), Object::String(r)) => l == r,
+            (Object::Identifier(l), Object::Identifier(r)) => l == r,
+            (Object::Int(l), Object::Int(r)) => l == r,
+            (Object::Number(l), Object::Number(r)) => l == r,
+            (Object::Decimal(l), Object::Decimal(r)) => l == r,
+            (Object::Bool(l), Object::Bool(r)) => l == r,
+            (Object::Native(l), Object::Native(r)) => l.name == r.name,
+            (Object::List(_), Object::List(_)) | (Object::Map(_), Object::Map(_)) => {
+                self.eq_nested(other, &mut vec![])
+            }
+            (Object::Nil, Object::Nil) => true,
+            _ => match (self.whole_number(), other.whole_number()) {
+                (Some(l), Some(r)) => l == r,
+                _ => false,
+            },
+        }
+    }
+}
+
+/// `Eq` only holds for hashable values; maps never store any other key.
+impl Eq for Object {}
+
+impl Hash for Object {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Whole numbers are equal across types, so they all hash like the
+        // matching `Int`, or like a `BigInt` if they don't fit in one. This
+        // also makes -0.0 hash like 0.
+        match self {
+            Object::Number(_) | Object::Decimal(_) => {
+                if let Some(whole) = self.whole_number() {
+                    return Object::BigInt(whole).hash(state);
+                }
+            }
+            Object::BigInt(big) => {
+                if let Some(int) = big.to_i64() {
+                    return Object::Int(int).hash(state);
+                }
+            }
+            _ => {}
+        }
+
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Object::String(str) | Object::Identifier(str) => str.hash(state),
+            Object::Number(num) => num.to_bits().hash(state),
+            Object::Int(int) => int.hash(state),
+            Object::BigInt(big) => big.hash(state),
+            Object::Decimal(dec) => dec.normalize().hash(state),
+            Object::Bool(b) => b.hash(state),
+            Object::List(list) => Rc::as_ptr(list).hash(state),
+            Object::Map(map) => Rc::as_ptr(map).hash(state),
+            Object::Native(native) => native.name.hash(state),
+            Object::Nil => {}
+        }
+    }
+}
+
+impl Object {
+    /// Writes a value that may contain lists and maps. `seen` holds the
+    /// containers being written, so one that contains itself prints as
+    /// `[...]` or `{...}` instead of recursing forever.
+    pub(crate) fn fmt_nested(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        seen: &mut Vec<*const ()>,
+    ) -> fmt::Result {
+        match self {
+            Object::List(list) => {
+                let ptr = Rc::as_ptr(list).cast::<()>();
+                if seen.contains(&ptr) {
+                    return f.write_str("[...]");
+                }
+                seen.push(ptr);
+                f.write_str("[")?;
+                for (i, item) in list.borrow().iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    item.fmt_nested(f, seen)?;
+                }
+                seen.pop();
+                f.write_str("]")
+            }
+            Object::Map(map) => {
+                let ptr = Rc::as_ptr(map).cast::<()>();
+                if seen.contains(&ptr) {
+                    return f.write_str("{...}");
+                }
+                seen.push(ptr);
+                map.borrow().fmt_nested(f, seen)?;
+                seen.pop();
+                Ok(())
+            }
+            _ => write!(f, "{}", self),
+        }
+    }
+
+    /// Compares values that may contain lists and maps. `seen` holds the
+    /// pairs of containers being compared. Meeting a pair again means both
+    /// sides loop back the same way, so it counts as equal.
+    pub(crate) fn eq_nested(&self, other: &Object, seen: &mut Vec<(*const (), *const ())>) -> bool {
+        match (self, other) {
+            (Object::List(l), Object::List(r)) => {
+                let pair = (Rc::as_ptr(l).cast::<()>(), Rc::as_ptr(r).cast::<()>());
+                if seen.contains(&pair) {
+                    return true;
+                }
+                seen.push(pair);
+                let (l, r) = (l.borrow(), r.borrow());
+                let equal =
+                    l.len() == r.len() && l.iter().zip(r.iter()).all(|(l, r)| l.eq_nested(r, seen));
+                seen.pop();
+                equal
+            }
+            (Object::Map(l), Object::Map(r)) => {
+                let pair = (Rc::as_ptr(l).cast::<()>(), Rc::as_ptr(r).cast::<()>());
+                if seen.contains(&pair) {
+                    return true;
+                }
+                seen.push(pair);
+                let equal = l.borrow().eq_nested(&r.borrow(), seen);
+                seen.pop();
+                equal
+            }
+            _ => self == other,
+        }
+    }
 }
 
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Object::String(str) => f.write_str(str),
-            Object::Number(num) => f.write_str(&num.to_string()),
+            Object::Number(num) => {
+                let str = num.to_string();
+                f.write_str(&str)?;
+                // Keep floats visibly distinct from ints: 3.0, not 3.
+                if num.is_finite() && !str.contains('.') {
+                    f.write_str(".0")?;
+                }
+                Ok(())
+            }
+            Object::Int(int) => f.write_str(&int.to_string()),
+            Object::BigInt(big) => f.write_str(&big.to_string()),
+            Object::Decimal(dec) => f.write_str(&dec.to_string()),
             Object::Identifier(ident) => f.write_str(ident),
             Object::Bool(b) => f.write_str(&b.to_string()),
+            Object::List(_) | Object::Map(_) => self.fmt_nested(f, &mut vec![]),
+            Object::Native(native) => write!(f, "<native fn {}>", native.name),
             Object::Nil => f.write_str("nil"),
         }
     }
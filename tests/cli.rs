@@ -1,4 +1,4 @@
-use std::process::Output;
+use std::{ffi::OsStr, process::Output};
 
 use assert_cmd::cargo::CommandCargoExt;
 use insta::{assert_yaml_snapshot, glob};
@@ -12,31 +12,40 @@ struct TestOutput {
     stderr: Vec<String>,
 }
 
+fn run(args: &[&OsStr]) -> TestOutput {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+
+    let Output {
+        status,
+        stdout,
+        stderr,
+    } = cmd.args(args).output().unwrap();
+
+    TestOutput {
+        status: status.code().unwrap(),
+        stdout: String::from_utf8_lossy(&stdout)
+            .to_string()
+            .lines()
+            .map(|x| x.to_owned())
+            .collect(),
+        stderr: String::from_utf8_lossy(&stderr)
+            .to_string()
+            .lines()
+            .map(|x| x.to_owned())
+            .collect(),
+    }
+}
+
 #[test]
 fn reference_files() {
     glob!("../test-files", "**/*.lox", |path| {
-        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
-
-        let Output {
-            status,
-            stdout,
-            stderr,
-        } = cmd.arg(path).output().unwrap();
-
-        let test_output = TestOutput {
-            status: status.code().unwrap(),
-            stdout: String::from_utf8_lossy(&stdout)
-                .to_string()
-                .lines()
-                .map(|x| x.to_owned())
-                .collect(),
-            stderr: String::from_utf8_lossy(&stderr)
-                .to_string()
-                .lines()
-                .map(|x| x.to_owned())
-                .collect(),
-        };
+        assert_yaml_snapshot!(run(&[path.as_os_str()]));
+    });
+}
 
-        assert_yaml_snapshot!(test_output);
+#[test]
+fn check_files() {
+    glob!("../check-files", "**/*.lox", |path| {
+        assert_yaml_snapshot!(run(&[OsStr::new("check"), path.as_os_str()]));
     });
 }